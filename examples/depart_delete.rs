@@ -1,7 +1,7 @@
 use anyhow::Result;
 use dotenv::dotenv;
 use rtxmail::{
-    client::{self, Exmailer},
+    client::{self, Exmailer, ExmailerExt},
     models::Department,
     Client,
 };
@@ -41,17 +41,10 @@ async fn main() -> Result<()> {
         delete_department(&c, email_depart, true, true).await?;
     }
 
-    // for depart in resp {
-    //     // if depart.name == "海外华人销转中心_2256571" {
-    //     delete_department(&c, depart, true, true).await?;
-    //     // }
-    // }
-
     Ok(())
 }
 
-/// 循环删除当前部门数据与所有子级部门数据
-#[async_recursion::async_recursion]
+/// 删除当前部门数据与所有子级部门数据，子部门先于父部门处理
 async fn delete_department(
     c: &Client,
     d: Department,
@@ -62,18 +55,26 @@ async fn delete_department(
         return Ok(c.delete_department(d.id).await?);
     }
     info!("处理企业邮箱部门数据: [Id: {}, Name: {}]", d.id, d.name);
-    let departs = c.list_department(Some(d.id)).await?;
-    for depart in departs {
-        if depart.id != d.id {
-            delete_department(c, depart, delete_child_department, true).await?;
-        }
+    let tree = c.get_department_tree(Some(d.id)).await?;
+    if !tree.is_consistent() {
+        info!(
+            "部门数据不完整, 孤立部门: {:?}, 循环部门: {:?}",
+            tree.orphans(),
+            tree.cycles()
+        );
     }
-    if !delete_root {
-        return Ok(());
+    for depart in tree.dfs_post_order() {
+        if depart.id == d.id && !delete_root {
+            continue;
+        }
+        info!(
+            "删除企业邮箱部门数据: [Id: {}, Path: {}]",
+            depart.id,
+            tree.path(depart.id).unwrap_or_default()
+        );
+        let users = c.get_department_user(depart.id, Some(true)).await?;
+        info!("users: {}", serde_json::to_string(&users)?);
+        // c.delete_department(depart.id).await?;
     }
-    info!("删除企业邮箱部门数据: [Id: {}, Name: {}]", d.id, d.name);
-    let users = c.get_department_user(d.id, Some(true)).await?;
-    info!("users: {}", serde_json::to_string(&users)?);
-    // Ok(c.delete_department(d.id).await?)
     Ok(())
 }
@@ -19,12 +19,8 @@ async fn main() -> Result<()> {
     let resp = c.get_user("shenshouer2955@xxx.com").await;
 
     match resp {
-        Err(err) => {
-            let err_msg = format!("{}", err);
-            if err_msg.contains("userid not found") {
-                println!("未找到用户",)
-            }
-        }
+        Err(err) if err.is_not_found() => println!("未找到用户"),
+        Err(err) => return Err(err.into()),
         Ok(user) => println!("{}", serde_json::to_string(&user)?),
     }
 
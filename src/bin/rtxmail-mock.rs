@@ -0,0 +1,54 @@
+//! 模拟腾讯企业邮箱接口的本地服务
+//!
+//! ```text
+//! rtxmail-mock [--addr 127.0.0.1:8080] [--corp-id ID] [--corp-secret SECRET]
+//!              [--token-ttl 秒] [--latency-ms 毫秒] [--error-rate 0.0~1.0] [--error-code 错误码]
+//! ```
+
+use rtxmail::testing::server::{MockConfig, MockExmailServer};
+use std::{env, process, str::FromStr, time::Duration};
+use tokio::net::TcpListener;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: rtxmail-mock [--addr ADDR] [--corp-id ID] [--corp-secret SECRET] \
+         [--token-ttl SECS] [--latency-ms MS] [--error-rate RATE] [--error-code CODE]"
+    );
+    process::exit(2)
+}
+
+fn parse<T: FromStr>(name: &str, value: Option<String>) -> T {
+    match value.map(|v| v.parse()) {
+        Some(Ok(value)) => value,
+        _ => {
+            eprintln!("invalid value for {name}");
+            usage()
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> rtxmail::errs::Result<()> {
+    let mut addr = "127.0.0.1:8080".to_owned();
+    let mut config = MockConfig::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--addr" => addr = parse(&arg, args.next()),
+            "--corp-id" => config.corp_id = parse(&arg, args.next()),
+            "--corp-secret" => config.corp_secret = parse(&arg, args.next()),
+            "--token-ttl" => config.token_ttl = Duration::from_secs(parse(&arg, args.next())),
+            "--latency-ms" => config.latency = Duration::from_millis(parse(&arg, args.next())),
+            "--error-rate" => config.error_rate = parse(&arg, args.next()),
+            "--error-code" => config.error_code = parse::<i64>(&arg, args.next()).into(),
+            _ => usage(),
+        }
+    }
+
+    let listener = TcpListener::bind(&addr).await?;
+    println!(
+        "rtxmail-mock listening on http://{}",
+        listener.local_addr()?
+    );
+    MockExmailServer::new(config).serve(listener).await
+}
@@ -0,0 +1,289 @@
+use super::payload_fields;
+use crate::errs::{new_invalid_callback_message, Error, Result};
+use aes::{
+    cipher::{block_padding::NoPadding, BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes256,
+};
+use base64::{
+    alphabet,
+    engine::{GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use sha1::{Digest, Sha1};
+
+/// EncodingAESKey末尾补`=`后不一定是规范的base64，解码时允许多余的尾部比特
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_allow_trailing_bits(true),
+);
+
+/// 加密前按32字节进行PKCS#7填充
+const PADDING_BLOCK: usize = 32;
+
+/// 回调消息的签名校验及加解密
+///
+/// 明文格式为`random(16字节) + msg_len(4字节网络序) + msg + corp_id`，
+/// 使用AES-256-CBC加密，key为EncodingAESKey的base64解码，iv为key的前16字节
+#[derive(Clone)]
+pub struct CallbackCrypto {
+    token: String,
+    key: [u8; 32],
+    corp_id: String,
+}
+
+impl std::fmt::Debug for CallbackCrypto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackCrypto")
+            .field("corp_id", &self.corp_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CallbackCrypto {
+    /// `token`及`encoding_aes_key`为管理后台回调配置中的Token与EncodingAESKey
+    pub fn new(
+        token: impl Into<String>,
+        encoding_aes_key: &str,
+        corp_id: impl Into<String>,
+    ) -> Result<Self> {
+        let key = BASE64
+            .decode(format!("{encoding_aes_key}="))
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .ok_or_else(|| new_invalid_callback_message("EncodingAESKey must be 43 characters"))?;
+
+        Ok(CallbackCrypto {
+            token: token.into(),
+            key,
+            corp_id: corp_id.into(),
+        })
+    }
+
+    /// 计算签名：token、timestamp、nonce及密文按字典序排序拼接后的sha1
+    pub fn signature(&self, timestamp: &str, nonce: &str, encrypt: &str) -> String {
+        let mut parts = [self.token.as_str(), timestamp, nonce, encrypt];
+        parts.sort_unstable();
+        Sha1::digest(parts.concat())
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    /// 校验签名
+    pub fn verify_signature(
+        &self,
+        signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        encrypt: &str,
+    ) -> Result<()> {
+        let expected = self.signature(timestamp, nonce, encrypt);
+        if constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            Ok(())
+        } else {
+            Err(Error::InvalidSignature)
+        }
+    }
+
+    /// 解密密文，并校验其中的corp_id
+    pub fn decrypt(&self, encrypt: &str) -> Result<String> {
+        let data = BASE64
+            .decode(encrypt.trim())
+            .map_err(|err| new_invalid_callback_message(err.to_string()))?;
+        let mut plain = cbc::Decryptor::<Aes256>::new(&self.key.into(), &self.iv().into())
+            .decrypt_padded_vec_mut::<NoPadding>(&data)
+            .map_err(|_| new_invalid_callback_message("ciphertext is not block aligned"))?;
+
+        let pad = plain.last().copied().unwrap_or_default() as usize;
+        if pad == 0
+            || pad > PADDING_BLOCK
+            || pad > plain.len()
+            || plain[plain.len() - pad..]
+                .iter()
+                .any(|&b| b as usize != pad)
+        {
+            return Err(new_invalid_callback_message("invalid padding"));
+        }
+        plain.truncate(plain.len() - pad);
+        if plain.len() < 20 {
+            return Err(new_invalid_callback_message("message too short"));
+        }
+        let len = u32::from_be_bytes([plain[16], plain[17], plain[18], plain[19]]) as usize;
+        if plain.len() < 20 + len {
+            return Err(new_invalid_callback_message("invalid message length"));
+        }
+        let (msg, corp_id) = plain[20..].split_at(len);
+        if corp_id != self.corp_id.as_bytes() {
+            return Err(new_invalid_callback_message("corp_id mismatch"));
+        }
+        String::from_utf8(msg.to_vec()).map_err(|err| new_invalid_callback_message(err.to_string()))
+    }
+
+    /// 加密消息，返回base64编码的密文
+    pub fn encrypt(&self, msg: &str) -> String {
+        let mut random = [0u8; 16];
+        fastrand::fill(&mut random);
+        self.encrypt_with_random(msg, random)
+    }
+
+    fn encrypt_with_random(&self, msg: &str, random: [u8; 16]) -> String {
+        let mut plain = random.to_vec();
+        plain.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+        plain.extend_from_slice(msg.as_bytes());
+        plain.extend_from_slice(self.corp_id.as_bytes());
+        let pad = PADDING_BLOCK - plain.len() % PADDING_BLOCK;
+        plain.resize(plain.len() + pad, pad as u8);
+
+        let data = cbc::Encryptor::<Aes256>::new(&self.key.into(), &self.iv().into())
+            .encrypt_padded_vec_mut::<NoPadding>(&plain);
+        BASE64.encode(data)
+    }
+
+    /// 验证回调地址：校验签名后解密`echostr`，解密结果需原样返回
+    pub fn verify_url(
+        &self,
+        signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        echostr: &str,
+    ) -> Result<String> {
+        self.verify_signature(signature, timestamp, nonce, echostr)?;
+        self.decrypt(echostr)
+    }
+
+    /// 解密推送的消息体，`body`为包含`Encrypt`字段的xml或json
+    pub fn decrypt_message(
+        &self,
+        signature: &str,
+        timestamp: &str,
+        nonce: &str,
+        body: &str,
+    ) -> Result<String> {
+        let fields = payload_fields(body);
+        let encrypt = fields
+            .get("Encrypt")
+            .ok_or_else(|| new_invalid_callback_message("missing Encrypt field"))?;
+        self.verify_signature(signature, timestamp, nonce, encrypt)?;
+        self.decrypt(encrypt)
+    }
+
+    /// 加密被动回复的消息，返回xml格式的响应体
+    pub fn encrypt_message(&self, msg: &str, timestamp: &str, nonce: &str) -> String {
+        let encrypt = self.encrypt(msg);
+        let signature = self.signature(timestamp, nonce, &encrypt);
+        format!(
+            "<xml><Encrypt><![CDATA[{encrypt}]]></Encrypt><MsgSignature><![CDATA[{signature}]]></MsgSignature><TimeStamp>{timestamp}</TimeStamp><Nonce><![CDATA[{nonce}]]></Nonce></xml>"
+        )
+    }
+
+    fn iv(&self) -> [u8; 16] {
+        let mut iv = [0u8; 16];
+        iv.copy_from_slice(&self.key[..16]);
+        iv
+    }
+}
+
+/// 比较耗时与内容无关的字节比较，避免通过响应时间逐字节猜测签名
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 官方文档中回调地址验证的示例参数
+    const CORP_ID: &str = "wx5823bf96d3bd56c7";
+    const TIMESTAMP: &str = "1409659589";
+    const NONCE: &str = "263014780";
+    const ECHOSTR: &str =
+        "P9nAzCzyDtyTWESHep1vC5X9xho/qYX3Zpb4yKa9SKld1DsH3Iyt3tP3zNdtp+4RPcs8TgAE7OaBO+FZXvnaqQ==";
+    const SIGNATURE: &str = "5c45ff5e21c57e6ad56bac8758b79b1d9ac89fd3";
+
+    fn crypto() -> CallbackCrypto {
+        CallbackCrypto::new(
+            "QDG6eK",
+            "jWmYm7qr5nMoAUwZRjGtBxmz3KA1tkAj3ykkR6q2B2C",
+            CORP_ID,
+        )
+        .unwrap()
+    }
+
+    /// 按指定填充字节加密，用于构造非法填充的密文
+    fn encrypt_padded(crypto: &CallbackCrypto, msg: &str, padding: &[u8]) -> String {
+        let mut plain = vec![0u8; 16];
+        plain.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+        plain.extend_from_slice(msg.as_bytes());
+        plain.extend_from_slice(crypto.corp_id.as_bytes());
+        plain.extend_from_slice(padding);
+        let data = cbc::Encryptor::<Aes256>::new(&crypto.key.into(), &crypto.iv().into())
+            .encrypt_padded_vec_mut::<NoPadding>(&plain);
+        BASE64.encode(data)
+    }
+
+    #[test]
+    fn official_url_vector() {
+        let crypto = crypto();
+        assert_eq!(crypto.signature(TIMESTAMP, NONCE, ECHOSTR), SIGNATURE);
+        assert_eq!(
+            crypto
+                .verify_url(SIGNATURE, TIMESTAMP, NONCE, ECHOSTR)
+                .unwrap(),
+            "1616140317555161061"
+        );
+    }
+
+    #[test]
+    fn reject_bad_signature_and_corp_id() {
+        let crypto = crypto();
+        let err = crypto
+            .verify_url("bad", TIMESTAMP, NONCE, ECHOSTR)
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidSignature));
+        let mut tampered = SIGNATURE.to_owned();
+        tampered.replace_range(39.., if SIGNATURE.ends_with('0') { "1" } else { "0" });
+        assert!(matches!(
+            crypto.verify_signature(&tampered, TIMESTAMP, NONCE, ECHOSTR),
+            Err(Error::InvalidSignature)
+        ));
+
+        let other = CallbackCrypto::new(
+            "QDG6eK",
+            "jWmYm7qr5nMoAUwZRjGtBxmz3KA1tkAj3ykkR6q2B2C",
+            "other",
+        )
+        .unwrap();
+        assert!(matches!(
+            other.decrypt(ECHOSTR).unwrap_err(),
+            Error::InvalidCallbackMessage { .. }
+        ));
+        assert!(CallbackCrypto::new("QDG6eK", "short", CORP_ID).is_err());
+    }
+
+    #[test]
+    fn reject_invalid_padding() {
+        let crypto = crypto();
+        // 16 + 4 + 7 + 18 = 45字节，补齐到64字节需要19字节填充
+        let valid = encrypt_padded(&crypto, "success", &[19; 19]);
+        assert_eq!(crypto.decrypt(&valid).unwrap(), "success");
+
+        let mut padding = [19; 19];
+        padding[0] = 0;
+        let tampered = encrypt_padded(&crypto, "success", &padding);
+        assert!(matches!(
+            crypto.decrypt(&tampered).unwrap_err(),
+            Error::InvalidCallbackMessage { .. }
+        ));
+    }
+
+    #[test]
+    fn encrypt_round_trip() {
+        let crypto = crypto();
+        let reply = crypto.encrypt_message("success", "1409659813", "1372623149");
+        let fields = super::super::xml_fields(&reply);
+        let msg = crypto
+            .decrypt_message(&fields["MsgSignature"], "1409659813", "1372623149", &reply)
+            .unwrap();
+        assert_eq!(msg, "success");
+    }
+}
@@ -0,0 +1,152 @@
+use super::payload_fields;
+use crate::errs::{new_invalid_callback_message, Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// 解密后的回调事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CallbackEvent {
+    /// 新邮件通知
+    NewMail {
+        /// 收件成员
+        user_id: String,
+        /// 发件人
+        from: String,
+        /// 邮件主题
+        subject: String,
+        /// 收信时间
+        time: DateTime<Utc>,
+    },
+    /// 新增成员
+    CreateUser { user_id: String },
+    /// 更新成员
+    UpdateUser { user_id: String },
+    /// 删除成员
+    DeleteUser { user_id: String },
+    /// 新增部门
+    CreateDepartment { id: u64 },
+    /// 更新部门
+    UpdateDepartment { id: u64 },
+    /// 删除部门
+    DeleteDepartment { id: u64 },
+    /// 未收录的事件，保留全部原始字段
+    Unknown {
+        event: String,
+        fields: HashMap<String, String>,
+    },
+}
+
+impl CallbackEvent {
+    /// 解析解密后的推送内容，支持xml及json格式
+    pub fn parse(payload: &str) -> Result<CallbackEvent> {
+        let mut fields = payload_fields(payload);
+        let Some(event) = fields.get("Event").cloned() else {
+            return Err(new_invalid_callback_message("missing Event field"));
+        };
+        let mut take = |name: &str| {
+            fields
+                .remove(name)
+                .ok_or_else(|| new_invalid_callback_message(format!("missing {name} field")))
+        };
+
+        let event = match event.as_str() {
+            "new_mail" => {
+                let time = take("Time")?;
+                CallbackEvent::NewMail {
+                    user_id: take("UserID")?,
+                    from: take("From")?,
+                    subject: take("Title").unwrap_or_default(),
+                    time: time
+                        .parse()
+                        .ok()
+                        .and_then(|ts| DateTime::from_timestamp(ts, 0))
+                        .ok_or_else(|| {
+                            new_invalid_callback_message(format!("invalid Time: {time}"))
+                        })?,
+                }
+            }
+            "change_contact" => {
+                let change_type = take("ChangeType")?;
+                match change_type.as_str() {
+                    "create_user" => CallbackEvent::CreateUser {
+                        user_id: take("UserID")?,
+                    },
+                    "update_user" => CallbackEvent::UpdateUser {
+                        user_id: take("UserID")?,
+                    },
+                    "delete_user" => CallbackEvent::DeleteUser {
+                        user_id: take("UserID")?,
+                    },
+                    "create_party" => CallbackEvent::CreateDepartment {
+                        id: parse_id(take("Id")?)?,
+                    },
+                    "update_party" => CallbackEvent::UpdateDepartment {
+                        id: parse_id(take("Id")?)?,
+                    },
+                    "delete_party" => CallbackEvent::DeleteDepartment {
+                        id: parse_id(take("Id")?)?,
+                    },
+                    _ => {
+                        fields.insert("ChangeType".to_owned(), change_type);
+                        CallbackEvent::Unknown { event, fields }
+                    }
+                }
+            }
+            _ => CallbackEvent::Unknown { event, fields },
+        };
+        Ok(event)
+    }
+}
+
+fn parse_id(id: String) -> Result<u64> {
+    id.parse()
+        .map_err(|_| new_invalid_callback_message(format!("invalid department id: {id}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_events() {
+        let event = CallbackEvent::parse(
+            "<xml><Event><![CDATA[change_contact]]></Event><ChangeType><![CDATA[create_user]]></ChangeType><UserID><![CDATA[zhangsan@gzdev.com]]></UserID></xml>",
+        )
+        .unwrap();
+        assert_eq!(
+            event,
+            CallbackEvent::CreateUser {
+                user_id: "zhangsan@gzdev.com".to_owned()
+            }
+        );
+
+        let event = CallbackEvent::parse(
+            r#"{"Event": "change_contact", "ChangeType": "delete_party", "Id": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(event, CallbackEvent::DeleteDepartment { id: 2 });
+
+        let event = CallbackEvent::parse(
+            "<xml><Event>new_mail</Event><UserID>zhangsan@gzdev.com</UserID><From>lisi@gzdev.com</From><Title>周报</Title><Time>1656633600</Time></xml>",
+        )
+        .unwrap();
+        match event {
+            CallbackEvent::NewMail { subject, time, .. } => {
+                assert_eq!(subject, "周报");
+                assert_eq!(time.timestamp(), 1656633600);
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        match CallbackEvent::parse("<xml><Event>other</Event><Foo>bar</Foo></xml>").unwrap() {
+            CallbackEvent::Unknown { event, fields } => {
+                assert_eq!(event, "other");
+                assert_eq!(fields["Foo"], "bar");
+            }
+            event => panic!("unexpected event: {event:?}"),
+        }
+
+        assert!(CallbackEvent::parse("<xml></xml>").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use super::{CallbackCrypto, CallbackEvent};
+use crate::errs::Error;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use std::{future::Future, sync::Arc};
+use tracing::warn;
+
+/// 回调请求的查询参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct CallbackQuery {
+    pub msg_signature: String,
+    pub timestamp: String,
+    pub nonce: String,
+    /// 仅验证回调地址时携带
+    pub echostr: Option<String>,
+}
+
+struct CallbackState<F> {
+    crypto: CallbackCrypto,
+    handler: F,
+}
+
+/// 创建回调路由，挂载在回调地址上
+///
+/// GET请求用于验证回调地址，POST请求解密为[`CallbackEvent`]后交给`handler`处理。
+/// 签名错误返回403，消息格式错误返回400
+pub fn callback_router<F, Fut>(crypto: CallbackCrypto, handler: F) -> Router
+where
+    F: Fn(CallbackEvent) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let state = Arc::new(CallbackState { crypto, handler });
+    Router::new()
+        .route("/", get(verify_url::<F>).post(receive::<F, Fut>))
+        .with_state(state)
+}
+
+async fn verify_url<F>(
+    State(state): State<Arc<CallbackState<F>>>,
+    Query(query): Query<CallbackQuery>,
+) -> Response {
+    let Some(echostr) = query.echostr else {
+        return (StatusCode::BAD_REQUEST, "missing echostr").into_response();
+    };
+    match state.crypto.verify_url(
+        &query.msg_signature,
+        &query.timestamp,
+        &query.nonce,
+        &echostr,
+    ) {
+        Ok(echo) => echo.into_response(),
+        Err(err) => error_response(err),
+    }
+}
+
+async fn receive<F, Fut>(
+    State(state): State<Arc<CallbackState<F>>>,
+    Query(query): Query<CallbackQuery>,
+    body: String,
+) -> Response
+where
+    F: Fn(CallbackEvent) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let event = state
+        .crypto
+        .decrypt_message(&query.msg_signature, &query.timestamp, &query.nonce, &body)
+        .and_then(|payload| CallbackEvent::parse(&payload));
+    match event {
+        Ok(event) => {
+            (state.handler)(event).await;
+            "success".into_response()
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+fn error_response(err: Error) -> Response {
+    warn!("invalid callback request: {err}");
+    let status = match err {
+        Error::InvalidSignature => StatusCode::FORBIDDEN,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    (status, err.to_string()).into_response()
+}
@@ -0,0 +1,94 @@
+//! 事件回调
+//!
+//! 腾讯企业邮箱将成员、部门变更及新邮件等通知加密后推送到企业配置的回调地址。
+//! [`CallbackCrypto`]负责校验签名及加解密消息，[`CallbackEvent`]为解密后的事件。
+//!
+//! ```no_run
+//! use rtxmail::callback::{CallbackCrypto, CallbackEvent};
+//!
+//! # fn run(body: &str) -> rtxmail::errs::Result<()> {
+//! let crypto = CallbackCrypto::new("token", "jWmYm7qr5nMoAUwZRjGtBxmz3KA1tkAj3ykkR6q2B2C", "corp_id")?;
+//! let payload = crypto.decrypt_message("msg_signature", "timestamp", "nonce", body)?;
+//! match CallbackEvent::parse(&payload)? {
+//!     CallbackEvent::CreateUser { user_id } => println!("新成员 {user_id}"),
+//!     event => println!("{event:?}"),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod crypto;
+pub use crypto::*;
+
+mod event;
+pub use event::*;
+
+#[cfg(feature = "axum")]
+mod handler;
+#[cfg(feature = "axum")]
+pub use handler::*;
+
+use std::collections::HashMap;
+
+/// 解析`<xml><Name>value</Name>...</xml>`形式的单层xml，值可以是CDATA
+fn xml_fields(xml: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    let xml = xml.trim();
+    let mut rest = xml
+        .strip_prefix("<xml>")
+        .and_then(|s| s.strip_suffix("</xml>"))
+        .unwrap_or(xml);
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let name = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+        let close = format!("</{name}>");
+        let Some(value_end) = rest.find(&close) else {
+            continue;
+        };
+        let value = rest[..value_end].trim();
+        let value = value
+            .strip_prefix("<![CDATA[")
+            .and_then(|v| v.strip_suffix("]]>"))
+            .unwrap_or(value);
+        fields.insert(name.to_owned(), value.to_owned());
+        rest = &rest[value_end + close.len()..];
+    }
+    fields
+}
+
+/// 解析推送内容的字段，同时兼容xml及json格式
+fn payload_fields(payload: &str) -> HashMap<String, String> {
+    if !payload.trim_start().starts_with('{') {
+        return xml_fields(payload);
+    }
+    let Ok(serde_json::Value::Object(map)) = serde_json::from_str(payload) else {
+        return HashMap::new();
+    };
+    map.into_iter()
+        .map(|(k, v)| match v {
+            serde_json::Value::String(s) => (k, s),
+            v => (k, v.to_string()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_xml_and_json_fields() {
+        let fields = xml_fields(
+            "<xml><ToUserName><![CDATA[corp]]></ToUserName>\n<CreateTime>1409659813</CreateTime></xml>",
+        );
+        assert_eq!(fields["ToUserName"], "corp");
+        assert_eq!(fields["CreateTime"], "1409659813");
+
+        let fields = payload_fields(r#"{"Encrypt": "abc", "AgentID": 1}"#);
+        assert_eq!(fields["Encrypt"], "abc");
+        assert_eq!(fields["AgentID"], "1");
+    }
+}
@@ -0,0 +1,358 @@
+//! 请求录制与回放
+//!
+//! 录制模式下将每次请求及响应写入cassette文件，回放模式下按请求匹配文件中的记录直接返回，
+//! 不再访问网络，用于在CI中离线、确定性地运行测试。
+//! 文件中的access_token、corpsecret及请求体中的password等字段均已脱敏，可以提交到代码仓库。
+//! 录制的记录先保存在内存中，调用[`Cassette::save`]或cassette被drop时写入文件。
+//!
+//! ```no_run
+//! use rtxmail::{cassette::Cassette, client::*};
+//! use std::sync::Arc;
+//!
+//! # async fn run() -> rtxmail::errs::Result<()> {
+//! // 文件不存在时录制，存在时回放
+//! let cassette = Arc::new(Cassette::auto("tests/cassettes/list_department.json")?);
+//! let client = Client::builder("corp_id", "corp_secret")
+//!     .cassette(cassette)
+//!     .build()?;
+//! let departments = client.list_department(None).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::errs::{new_http_error, Error, Result};
+use reqwest::{Method, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// 脱敏后的取值
+pub const REDACTED: &str = "REDACTED";
+
+/// 需要脱敏的查询参数及响应字段
+const SECRET_FIELDS: [&str; 2] = ["access_token", "corpsecret"];
+
+/// 默认需要脱敏的请求体字段
+pub const DEFAULT_SECRET_BODY_FIELDS: [&str; 1] = ["password"];
+
+/// cassette工作模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// 发送真实请求并记录
+    Record,
+    /// 只从记录中返回响应
+    Replay,
+}
+
+/// 一次请求及其响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// 接口路径，不包含接口地址
+    pub path: String,
+    /// 脱敏后的查询参数
+    pub query: BTreeMap<String, String>,
+    /// 脱敏后的请求体
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+    pub status: u16,
+    /// 脱敏后的响应内容
+    pub response: String,
+}
+
+impl Interaction {
+    fn matches(&self, other: &Interaction) -> bool {
+        self.method == other.method
+            && self.path == other.path
+            && self.query == other.query
+            && self.body == other.body
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    /// 回放时已使用过的记录
+    used: Vec<bool>,
+    /// 录制时是否有未写入文件的记录
+    dirty: bool,
+}
+
+/// 录制或回放请求的cassette文件
+#[derive(Debug)]
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    secret_body_fields: Vec<String>,
+    state: Mutex<State>,
+}
+
+impl Cassette {
+    /// 录制到`path`，已有文件将被覆盖
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Cassette {
+            path: path.into(),
+            mode: CassetteMode::Record,
+            secret_body_fields: default_secret_body_fields(),
+            state: Mutex::default(),
+        }
+    }
+
+    /// 从`path`回放
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file: CassetteFile = serde_json::from_slice(&std::fs::read(&path)?)?;
+        let used = vec![false; file.interactions.len()];
+        Ok(Cassette {
+            path,
+            mode: CassetteMode::Replay,
+            secret_body_fields: default_secret_body_fields(),
+            state: Mutex::new(State {
+                interactions: file.interactions,
+                used,
+                dirty: false,
+            }),
+        })
+    }
+
+    /// `path`存在时回放，否则录制
+    pub fn auto(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            Self::replay(path)
+        } else {
+            Ok(Self::record(path))
+        }
+    }
+
+    /// 设置需要脱敏的请求体字段，替换默认的[`DEFAULT_SECRET_BODY_FIELDS`]，
+    /// 任意层级中的同名字段均会被脱敏，录制与回放需使用相同的设置
+    pub fn secret_body_fields(
+        mut self,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.secret_body_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 已录制或加载的记录
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.state().interactions.clone()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 回放：按顺序返回第一条未使用且匹配的记录
+    pub(crate) fn replay_response(
+        &self,
+        method: &Method,
+        url: &Url,
+        body: Option<&Value>,
+    ) -> Result<String> {
+        let request = self.new_interaction(method, url, body, StatusCode::OK, String::new());
+        let mut state = self.state();
+        let State {
+            interactions, used, ..
+        } = &mut *state;
+        let Some(i) =
+            (0..interactions.len()).find(|&i| !used[i] && interactions[i].matches(&request))
+        else {
+            return Err(Error::CassetteMiss {
+                method: request.method,
+                url: format!("{}?{}", request.path, encode_query(&request.query)),
+            });
+        };
+        used[i] = true;
+
+        let interaction = &interactions[i];
+        let status = StatusCode::from_u16(interaction.status).unwrap_or(StatusCode::OK);
+        if !status.is_success() {
+            return Err(new_http_error(
+                url.to_string(),
+                status,
+                interaction.response.clone(),
+            ));
+        }
+        Ok(interaction.response.clone())
+    }
+
+    /// 录制：追加记录，写入文件见[`save`](Self::save)
+    pub(crate) fn record_response(
+        &self,
+        method: &Method,
+        url: &Url,
+        body: Option<&Value>,
+        status: StatusCode,
+        response: &str,
+    ) {
+        let interaction =
+            self.new_interaction(method, url, body, status, redact_response(response));
+        let mut state = self.state();
+        state.interactions.push(interaction);
+        state.dirty = true;
+    }
+
+    /// 将录制的记录写入文件，回放模式下不做任何操作
+    pub fn save(&self) -> Result<()> {
+        if self.mode != CassetteMode::Record {
+            return Ok(());
+        }
+        let mut state = self.state();
+        let file = CassetteFile {
+            interactions: state.interactions.clone(),
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&file)?)?;
+        state.dirty = false;
+        Ok(())
+    }
+
+    fn new_interaction(
+        &self,
+        method: &Method,
+        url: &Url,
+        body: Option<&Value>,
+        status: StatusCode,
+        response: String,
+    ) -> Interaction {
+        let query = url
+            .query_pairs()
+            .map(|(k, v)| {
+                let v = if SECRET_FIELDS.contains(&k.as_ref()) {
+                    REDACTED.to_owned()
+                } else {
+                    v.into_owned()
+                };
+                (k.into_owned(), v)
+            })
+            .collect();
+        let body = body.map(|body| {
+            let mut body = body.clone();
+            redact_body(&mut body, &self.secret_body_fields);
+            body
+        });
+        Interaction {
+            method: method.to_string(),
+            path: url.path().to_owned(),
+            query,
+            body,
+            status: status.as_u16(),
+            response,
+        }
+    }
+}
+
+impl Drop for Cassette {
+    fn drop(&mut self) {
+        if self.state().dirty {
+            if let Err(err) = self.save() {
+                tracing::warn!("failed to save cassette {}: {err}", self.path.display());
+            }
+        }
+    }
+}
+
+fn default_secret_body_fields() -> Vec<String> {
+    DEFAULT_SECRET_BODY_FIELDS.map(String::from).to_vec()
+}
+
+/// 将请求体中任意层级的`fields`字段替换为[`REDACTED`]
+fn redact_body(body: &mut Value, fields: &[String]) {
+    match body {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.iter().any(|f| f == key) {
+                    *value = Value::String(REDACTED.to_owned());
+                } else {
+                    redact_body(value, fields);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| redact_body(v, fields)),
+        _ => {}
+    }
+}
+
+fn encode_query(query: &BTreeMap<String, String>) -> String {
+    query
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// 响应为json时将其中的access_token替换为[`REDACTED`]
+fn redact_response(response: &str) -> String {
+    let Ok(Value::Object(mut map)) = serde_json::from_str::<Value>(response) else {
+        return response.to_owned();
+    };
+    let mut redacted = false;
+    for field in SECRET_FIELDS {
+        if let Some(value) = map.get_mut(field) {
+            *value = Value::String(REDACTED.to_owned());
+            redacted = true;
+        }
+    }
+    if redacted {
+        Value::Object(map).to_string()
+    } else {
+        response.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_secrets() {
+        let url =
+            Url::parse("https://api.exmail.qq.com/cgi-bin/gettoken?corpid=corp&corpsecret=secret")
+                .unwrap();
+        let cassette = Cassette::record("unused.json");
+        let interaction = cassette.new_interaction(
+            &Method::GET,
+            &url,
+            None,
+            StatusCode::OK,
+            redact_response(r#"{"errcode":0,"access_token":"token","expires_in":7200}"#),
+        );
+        assert_eq!(interaction.path, "/cgi-bin/gettoken");
+        assert_eq!(interaction.query["corpid"], "corp");
+        assert_eq!(interaction.query["corpsecret"], REDACTED);
+        assert!(!interaction.response.contains("\"token\""));
+        assert!(interaction.response.contains(REDACTED));
+        assert_eq!(redact_response("not json"), "not json");
+
+        let body = serde_json::json!({"userid": "zhangsan", "password": "secret"});
+        let interaction = cassette.new_interaction(
+            &Method::POST,
+            &url,
+            Some(&body),
+            StatusCode::OK,
+            String::new(),
+        );
+        assert_eq!(interaction.body.unwrap()["password"], REDACTED);
+    }
+}
@@ -0,0 +1,213 @@
+use super::{Client, ClientInner, DEFAULT_BASE_URL};
+use crate::{
+    cassette::Cassette,
+    errs::Result,
+    ratelimit::{RateLimit, RateLimiter},
+    retry::RetryPolicy,
+    token::{MemoryTokenStore, TokenStore, DEFAULT_REFRESH_MARGIN},
+    utils::http::{get_http_client, HttpOptions},
+};
+use reqwest::header::{HeaderName, HeaderValue};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+
+/// Client构建器
+///
+/// ```no_run
+/// # fn main() -> rtxmail::errs::Result<()> {
+/// use std::time::Duration;
+///
+/// let client = rtxmail::Client::builder("corp_id", "corp_secret")
+///     .base_url("http://127.0.0.1:8080")
+///     .connect_timeout(Duration::from_secs(3))
+///     .timeout(Duration::from_secs(10))
+///     .user_agent("my-sync/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientBuilder {
+    corp_id: String,
+    corp_secret: String,
+    base_url: String,
+    rate_limit: Option<RateLimit>,
+    endpoint_rate_limits: Vec<(String, RateLimit)>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    http: HttpOptions,
+    http_client: Option<reqwest::Client>,
+    token_refresh_margin: Duration,
+    token_store: Option<Arc<dyn TokenStore>>,
+    retry_policy: RetryPolicy,
+    cassette: Option<Arc<Cassette>>,
+}
+
+impl ClientBuilder {
+    pub(crate) fn new(corp_id: String, corp_secret: String) -> Self {
+        ClientBuilder {
+            corp_id,
+            corp_secret,
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            rate_limit: None,
+            endpoint_rate_limits: Vec::new(),
+            rate_limiter: None,
+            http: HttpOptions::default(),
+            http_client: None,
+            token_refresh_margin: DEFAULT_REFRESH_MARGIN,
+            token_store: None,
+            retry_policy: RetryPolicy::default(),
+            cassette: None,
+        }
+    }
+
+    /// 接口地址，默认为`https://api.exmail.qq.com`
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// 两次请求之间的最小间隔，等同于`rate_limit(RateLimit::every(interval))`
+    pub fn interval(self, interval: Duration) -> Self {
+        self.rate_limit(RateLimit::every(interval))
+    }
+
+    /// 所有接口共用的限流配置，默认不限流
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// 为指定接口(如`/cgi-bin/user/get`)单独限流
+    pub fn endpoint_rate_limit(mut self, path: impl Into<String>, limit: RateLimit) -> Self {
+        self.endpoint_rate_limits.push((path.into(), limit));
+        self
+    }
+
+    /// 使用已有的限流器，可在多个Client之间共享。设置后`rate_limit`等配置不再生效
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// 连接超时时间
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// 请求超时时间，从发起连接到读取完响应
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http.timeout = Some(timeout);
+        self
+    }
+
+    /// 代理地址，如`http://127.0.0.1:3128`。设置后将忽略PROXY环境变量
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.http.proxy = Some(proxy.into());
+        self
+    }
+
+    /// User-Agent
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.http.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// 每个请求都携带的自定义header
+    pub fn header(mut self, key: HeaderName, value: HeaderValue) -> Self {
+        self.http.headers.append(key, value);
+        self
+    }
+
+    /// 重试策略，默认对幂等请求最多尝试3次，不需要重试时设置为[`RetryPolicy::none`]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// access_token距离过期不足该时间时提前刷新，默认5分钟
+    pub fn token_refresh_margin(mut self, margin: Duration) -> Self {
+        self.token_refresh_margin = margin;
+        self
+    }
+
+    /// access_token存储，默认为进程内的[`MemoryTokenStore`]。
+    ///
+    /// 多个进程共享token时可使用[`FileTokenStore`](crate::token::FileTokenStore)
+    pub fn token_store(mut self, token_store: Arc<dyn TokenStore>) -> Self {
+        self.token_store = Some(token_store);
+        self
+    }
+
+    /// 使用调用方提供的http client，可与其他组件共享连接池。
+    ///
+    /// 设置后超时、代理、User-Agent及header等http配置将不再生效
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// 按cassette的模式录制或回放请求，见[`cassette`](crate::cassette)
+    pub fn cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// 构建Client，代理等配置不合法时返回错误
+    pub fn build(self) -> Result<Client> {
+        let http_client = match self.http_client {
+            Some(ref http_client) => http_client.clone(),
+            None => get_http_client(&self.http)?,
+        };
+        Ok(self.build_with(Ok(http_client)))
+    }
+
+    /// 构建Client，http client构建失败时推迟到请求时返回错误
+    pub(crate) fn build_lenient(self) -> Client {
+        let http_client = match self.http_client {
+            Some(ref http_client) => Ok(http_client.clone()),
+            None => get_http_client(&self.http).map_err(Arc::new),
+        };
+        self.build_with(http_client)
+    }
+
+    fn build_with(
+        self,
+        http_client: std::result::Result<reqwest::Client, Arc<reqwest::Error>>,
+    ) -> Client {
+        let rate_limiter = self.rate_limiter.unwrap_or_else(|| {
+            let limiter = match self.rate_limit {
+                Some(limit) => RateLimiter::new(limit),
+                None => RateLimiter::unlimited(),
+            };
+            let limiter = self
+                .endpoint_rate_limits
+                .into_iter()
+                .fold(limiter, |limiter, (path, limit)| {
+                    limiter.with_endpoint(path, limit)
+                });
+            Arc::new(limiter)
+        });
+
+        let inner = ClientInner {
+            corp_id: self.corp_id,
+            corp_secret: self.corp_secret,
+            base_url: self.base_url,
+            rate_limiter,
+            http_client,
+            retry_policy: self.retry_policy,
+            token_refresh_margin: self.token_refresh_margin,
+            token_store: self
+                .token_store
+                .unwrap_or_else(|| Arc::new(MemoryTokenStore::new())),
+            token_lock: Arc::new(Mutex::new(None)),
+            token_generation: Arc::default(),
+            cassette: self.cassette,
+        };
+
+        Client {
+            inner: Arc::new(inner),
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! 基于[`Exmailer`]单个接口组合出的批量操作
+
+use super::Exmailer;
+use crate::{
+    dto::*,
+    errs::{new_conflict, new_unexpected_response, Result},
+    models::*,
+};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use futures::{
+    future::BoxFuture,
+    stream::{self, StreamExt},
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 批量操作默认的并发数，实际请求频率仍受客户端限流控制
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// 读取-修改-写入群组成员时，因并发修改而重新读取的最大次数
+pub const GROUP_UPDATE_MAX_ATTEMPTS: usize = 3;
+
+/// 批量操作选项
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+    /// 同时进行中的请求数，为0时按1处理
+    pub concurrency: usize,
+    /// 出现失败后不再发起新的请求，已发出的请求仍会等待完成
+    pub stop_on_first_failure: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            concurrency: DEFAULT_CONCURRENCY,
+            stop_on_first_failure: false,
+        }
+    }
+}
+
+/// 批量操作结果
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// 已执行的userid及其结果，按输入顺序排列
+    pub results: Vec<(String, Result<()>)>,
+    /// 因提前停止而未执行的userid
+    pub skipped: Vec<String>,
+}
+
+impl BatchReport {
+    /// 全部执行且成功
+    pub fn is_success(&self) -> bool {
+        self.skipped.is_empty() && self.results.iter().all(|(_, r)| r.is_ok())
+    }
+
+    /// 成功的userid
+    pub fn succeeded(&self) -> impl Iterator<Item = &str> {
+        self.results
+            .iter()
+            .filter(|(_, r)| r.is_ok())
+            .map(|(id, _)| id.as_str())
+    }
+
+    /// 失败的userid及错误
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &crate::errs::Error)> {
+        self.results
+            .iter()
+            .filter_map(|(id, r)| r.as_ref().err().map(|err| (id.as_str(), err)))
+    }
+}
+
+/// 读取群组，按`add`增加或删除`items`后写回`members`对应的字段
+///
+/// 写入前重新读取群组，字段已被其他操作修改时重试，超过[`GROUP_UPDATE_MAX_ATTEMPTS`]次返回
+/// [`Error::Conflict`](crate::errs::Error::Conflict)
+async fn modify_group_members<E, T>(
+    client: &E,
+    group_id: &str,
+    members: fn(&Group) -> &Vec<T>,
+    set: fn(&mut ParamsUpdateGroup, Vec<T>),
+    items: &[T],
+    add: bool,
+) -> Result<()>
+where
+    E: Exmailer + Sync + ?Sized,
+    T: Clone + PartialEq + Send + Sync,
+{
+    for _ in 0..GROUP_UPDATE_MAX_ATTEMPTS {
+        let current = members(&client.get_group(group_id).await?).clone();
+        let mut updated = current.clone();
+        if add {
+            for item in items {
+                if !updated.contains(item) {
+                    updated.push(item.clone());
+                }
+            }
+        } else {
+            updated.retain(|item| !items.contains(item));
+        }
+        if updated == current {
+            return Ok(());
+        }
+
+        if members(&client.get_group(group_id).await?) != &current {
+            continue;
+        }
+        let mut params = ParamsUpdateGroup {
+            groupid: group_id.to_owned(),
+            ..Default::default()
+        };
+        set(&mut params, updated);
+        return client.update_group(params).await;
+    }
+
+    Err(new_conflict(group_id))
+}
+
+/// 按`options`并发执行`tasks`并汇总结果
+async fn run_batch(
+    tasks: Vec<(String, BoxFuture<'_, Result<()>>)>,
+    options: BatchOptions,
+) -> BatchReport {
+    let stop = AtomicBool::new(false);
+    let stop = &stop;
+    let tasks: Vec<_> = tasks
+        .into_iter()
+        .map(|(user_id, task)| async move {
+            if stop.load(Ordering::Acquire) {
+                return (user_id, None);
+            }
+            let result = task.await;
+            if result.is_err() && options.stop_on_first_failure {
+                stop.store(true, Ordering::Release);
+            }
+            (user_id, Some(result))
+        })
+        .collect();
+
+    let mut report = BatchReport::default();
+    let mut results = stream::iter(tasks).buffered(options.concurrency.max(1));
+    while let Some((user_id, result)) = results.next().await {
+        match result {
+            Some(result) => report.results.push((user_id, result)),
+            None => report.skipped.push(user_id),
+        }
+    }
+    report
+}
+
+/// [`Exmailer`]的扩展方法，所有实现了[`Exmailer`]的类型均可使用
+#[async_trait]
+pub trait ExmailerExt: Exmailer + Sync {
+    /// 并发查询多个成员的未读邮件数，结果与`user_ids`顺序一致，单个成员失败不影响其他成员
+    ///
+    /// `concurrency`为同时进行中的请求数，为0时按1处理
+    async fn get_new_mail_counts(
+        &self,
+        user_ids: &[&str],
+        begin_date: NaiveDate,
+        end_date: NaiveDate,
+        concurrency: usize,
+    ) -> Vec<(String, Result<NewMailCount>)> {
+        let tasks: Vec<_> = user_ids
+            .iter()
+            .map(|&user_id| async move {
+                let count = self.get_new_mail_count(user_id, begin_date, end_date).await;
+                (user_id.to_owned(), count)
+            })
+            .collect();
+
+        stream::iter(tasks)
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// 获取以`id`为根的部门树，未指定时获取整个企业的部门树
+    async fn get_department_tree(&self, id: Option<u64>) -> Result<DepartmentTree> {
+        let root = id.unwrap_or(1);
+        let departments = self.list_department(Some(root)).await?;
+        DepartmentTree::new(root, departments).ok_or_else(|| {
+            new_unexpected_response(
+                "/cgi-bin/department/list",
+                format!("department {root} is missing from the response"),
+            )
+        })
+    }
+
+    /// 批量创建成员，返回每个成员的创建结果
+    async fn create_users(
+        &self,
+        users: Vec<ParamsCreateUser>,
+        options: BatchOptions,
+    ) -> BatchReport {
+        let tasks = users
+            .into_iter()
+            .map(|params| (params.user_id.clone(), self.create_user(params)))
+            .collect();
+        run_batch(tasks, options).await
+    }
+
+    /// 批量更新成员，返回每个成员的更新结果
+    async fn update_users(
+        &self,
+        users: Vec<ParamsUpdateUser>,
+        options: BatchOptions,
+    ) -> BatchReport {
+        let tasks = users
+            .into_iter()
+            .map(|params| (params.user_id.clone(), self.update_user(params)))
+            .collect();
+        run_batch(tasks, options).await
+    }
+
+    /// 向群组添加成员帐号，已存在的成员忽略
+    async fn add_group_members(&self, group_id: &str, users: &[&str]) -> Result<()> {
+        let users: Vec<String> = users.iter().map(|u| u.to_string()).collect();
+        modify_group_members(
+            self,
+            group_id,
+            |g| &g.userlist,
+            |p, v| p.userlist = Some(v),
+            &users,
+            true,
+        )
+        .await
+    }
+
+    /// 从群组删除成员帐号，不存在的成员忽略
+    async fn remove_group_members(&self, group_id: &str, users: &[&str]) -> Result<()> {
+        let users: Vec<String> = users.iter().map(|u| u.to_string()).collect();
+        modify_group_members(
+            self,
+            group_id,
+            |g| &g.userlist,
+            |p, v| p.userlist = Some(v),
+            &users,
+            false,
+        )
+        .await
+    }
+
+    /// 向群组添加成员邮件群组
+    async fn add_group_subgroups(&self, group_id: &str, groups: &[&str]) -> Result<()> {
+        let groups: Vec<String> = groups.iter().map(|g| g.to_string()).collect();
+        modify_group_members(
+            self,
+            group_id,
+            |g| &g.grouplist,
+            |p, v| p.grouplist = Some(v),
+            &groups,
+            true,
+        )
+        .await
+    }
+
+    /// 从群组删除成员邮件群组
+    async fn remove_group_subgroups(&self, group_id: &str, groups: &[&str]) -> Result<()> {
+        let groups: Vec<String> = groups.iter().map(|g| g.to_string()).collect();
+        modify_group_members(
+            self,
+            group_id,
+            |g| &g.grouplist,
+            |p, v| p.grouplist = Some(v),
+            &groups,
+            false,
+        )
+        .await
+    }
+
+    /// 向群组添加成员部门
+    async fn add_group_departments(&self, group_id: &str, departments: &[u64]) -> Result<()> {
+        modify_group_members(
+            self,
+            group_id,
+            |g| &g.department,
+            |p, v| p.department = Some(v),
+            departments,
+            true,
+        )
+        .await
+    }
+
+    /// 从群组删除成员部门
+    async fn remove_group_departments(&self, group_id: &str, departments: &[u64]) -> Result<()> {
+        modify_group_members(
+            self,
+            group_id,
+            |g| &g.department,
+            |p, v| p.department = Some(v),
+            departments,
+            false,
+        )
+        .await
+    }
+}
+
+impl<T: Exmailer + Sync + ?Sized> ExmailerExt for T {}
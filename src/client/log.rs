@@ -0,0 +1,150 @@
+use super::{response::*, Client};
+use crate::{
+    dto::*,
+    errs::{new_invalid_argument, Result},
+    models::*,
+};
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate};
+use reqwest::Method;
+
+/// 日志接口单次查询的最大天数
+pub const LOG_QUERY_MAX_DAYS: i64 = 30;
+
+/// 将`[begin, end]`按最多`max_days`天切分为多个闭区间
+fn date_chunks(begin: NaiveDate, end: NaiveDate, max_days: i64) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut chunks = vec![];
+    let mut start = begin;
+    while start <= end {
+        let stop = (start + Duration::days(max_days - 1)).min(end);
+        chunks.push((start, stop));
+        start = stop + Duration::days(1);
+    }
+    chunks
+}
+
+/// 开始日期晚于结束日期时返回参数错误，避免分段查询时返回空结果
+fn check_date_range(begin: NaiveDate, end: NaiveDate) -> Result<()> {
+    if begin > end {
+        return Err(new_invalid_argument(format!(
+            "begin_date {begin} is after end_date {end}"
+        )));
+    }
+    Ok(())
+}
+
+/// 日志查询
+#[async_trait]
+pub trait LogApi {
+    /// 查询域名在日期范围内的收发信总量
+    async fn get_mail_status(&self, params: ParamsMailStatus) -> Result<MailStatus>;
+    /// 查询邮件收发日志
+    async fn list_mail_log(&self, params: ParamsMailLog) -> Result<Vec<MailLog>>;
+    /// 查询成员登录日志，日期范围超过[`LOG_QUERY_MAX_DAYS`]天时分段查询后合并
+    async fn list_login_log(&self, params: ParamsLoginLog) -> Result<Vec<LoginLog>>;
+    /// 查询批量任务日志
+    async fn list_batch_job_log(&self, params: ParamsBatchJobLog) -> Result<Vec<BatchJobLog>>;
+    /// 查询管理员操作日志
+    async fn list_operation_log(&self, params: ParamsOperationLog) -> Result<Vec<OperationLog>>;
+}
+
+#[async_trait]
+impl LogApi for Client {
+    async fn get_mail_status(&self, params: ParamsMailStatus) -> Result<MailStatus> {
+        check_date_range(params.begin_date, params.end_date)?;
+        self.request_idempotent(
+            Method::POST,
+            "/cgi-bin/log/mailstatus",
+            Some(serde_json::to_value(params)?),
+        )
+        .await
+    }
+
+    async fn list_mail_log(&self, params: ParamsMailLog) -> Result<Vec<MailLog>> {
+        check_date_range(params.begin_date, params.end_date)?;
+        let resp: MailLogResponse = self
+            .request_idempotent(
+                Method::POST,
+                "/cgi-bin/log/mail",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        Ok(resp.list)
+    }
+
+    async fn list_login_log(&self, params: ParamsLoginLog) -> Result<Vec<LoginLog>> {
+        check_date_range(params.begin_date, params.end_date)?;
+        let mut logs = vec![];
+        for (begin_date, end_date) in
+            date_chunks(params.begin_date, params.end_date, LOG_QUERY_MAX_DAYS)
+        {
+            let chunk = ParamsLoginLog {
+                begin_date,
+                end_date,
+                ..params.clone()
+            };
+            let resp: LoginLogResponse = self
+                .request_idempotent(
+                    Method::POST,
+                    "/cgi-bin/log/login",
+                    Some(serde_json::to_value(chunk)?),
+                )
+                .await?;
+            logs.extend(resp.list);
+        }
+
+        Ok(logs)
+    }
+
+    async fn list_batch_job_log(&self, params: ParamsBatchJobLog) -> Result<Vec<BatchJobLog>> {
+        check_date_range(params.begin_date, params.end_date)?;
+        let resp: BatchJobLogResponse = self
+            .request_idempotent(
+                Method::POST,
+                "/cgi-bin/log/batchjob",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        Ok(resp.list)
+    }
+
+    async fn list_operation_log(&self, params: ParamsOperationLog) -> Result<Vec<OperationLog>> {
+        check_date_range(params.begin_date, params.end_date)?;
+        let resp: OperationLogResponse = self
+            .request_idempotent(
+                Method::POST,
+                "/cgi-bin/log/operation",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        Ok(resp.list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn split_date_range() {
+        assert_eq!(
+            date_chunks(date("2022-07-01"), date("2022-07-01"), 30),
+            [(date("2022-07-01"), date("2022-07-01"))]
+        );
+        assert_eq!(
+            date_chunks(date("2022-07-01"), date("2022-08-14"), 30),
+            [
+                (date("2022-07-01"), date("2022-07-30")),
+                (date("2022-07-31"), date("2022-08-14")),
+            ]
+        );
+        assert!(date_chunks(date("2022-07-02"), date("2022-07-01"), 30).is_empty());
+    }
+}
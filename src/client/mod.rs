@@ -0,0 +1,724 @@
+use crate::{
+    cassette::Cassette,
+    errs::{ApiErrorCode, Error, Result},
+    ratelimit::{RateLimit, RateLimiter},
+    retry::RetryPolicy,
+    token::{Token, TokenStore},
+    utils::http::{do_http, PostParameters},
+};
+pub use crate::{dto::*, models::*};
+use async_trait::async_trait;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use tokio::sync::Mutex;
+use tokio::time::Duration;
+use tracing::{debug, warn};
+
+mod builder;
+pub use builder::ClientBuilder;
+
+mod ext;
+pub use ext::{
+    BatchOptions, BatchReport, ExmailerExt, DEFAULT_CONCURRENCY, GROUP_UPDATE_MAX_ATTEMPTS,
+};
+
+mod log;
+pub use log::{LogApi, LOG_QUERY_MAX_DAYS};
+
+mod response;
+use response::*;
+
+/// 默认接口地址
+pub const DEFAULT_BASE_URL: &str = "https://api.exmail.qq.com";
+
+/// 腾讯企业邮箱客户端
+///
+/// # 并发模型
+///
+/// `Client`内部通过`Arc`共享状态，`clone`只增加引用计数，clone出的Client共用同一个
+/// http连接池、token存储、限流器及重试策略，可以直接move到多个tokio任务中并发调用。
+///
+/// - access_token：未过期时并发请求只需从token存储中读取一次，不会排队等待刷新；
+///   需要刷新时只有一个任务发起刷新请求，其他任务等待刷新完成后读取新token，刷新失败时共享该错误
+/// - 限流：所有clone共用同一组令牌桶，并发请求按配置的频率排队，见[`RateLimit`]
+///
+/// ```no_run
+/// use futures::stream::{self, StreamExt};
+/// use rtxmail::{client::Exmailer, Client};
+///
+/// # async fn run() -> rtxmail::errs::Result<()> {
+/// let client = Client::builder("corp_id", "corp_secret").build()?;
+/// let user_ids = vec!["a@example.com", "b@example.com"];
+/// let users: Vec<_> = stream::iter(user_ids)
+///     .map(|user_id| {
+///         let client = client.clone();
+///         async move { client.get_user(user_id).await }
+///     })
+///     .buffer_unordered(8)
+///     .collect()
+///     .await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: Arc<ClientInner>,
+}
+
+#[derive(Debug, Clone)]
+struct ClientInner {
+    pub(crate) corp_id: String,
+    pub(crate) corp_secret: String,
+    /// 接口地址
+    pub(crate) base_url: String,
+    /// 限流器
+    pub(crate) rate_limiter: Arc<RateLimiter>,
+    /// 所有请求共用的http client，复用连接池。
+    /// 通过[`Client::new`]创建且构建失败时保存该错误，每次请求时返回
+    pub(crate) http_client: std::result::Result<reqwest::Client, Arc<reqwest::Error>>,
+    /// 重试策略
+    pub(crate) retry_policy: RetryPolicy,
+    /// 距离过期不足该时间时提前刷新token
+    pub(crate) token_refresh_margin: Duration,
+    /// token存储
+    pub(crate) token_store: Arc<dyn TokenStore>,
+    /// 刷新token时加锁，保证同时只有一个刷新请求，保存最近一次刷新失败的错误
+    token_lock: Arc<Mutex<Option<TokenRefreshFailure>>>,
+    /// 已完成的刷新次数，用于判断等待锁期间其他任务是否已完成刷新
+    token_generation: Arc<AtomicU64>,
+    /// 录制或回放请求
+    pub(crate) cassette: Option<Arc<Cassette>>,
+}
+
+impl Client {
+    /// 使用默认配置创建Client，等同于`Client::builder(corp_id, corp_secret).build()`
+    ///
+    /// http client初始化失败(如PROXY环境变量不合法)时不会panic，而是在每次请求时返回该错误，
+    /// 需要在创建时处理该错误请使用[`Client::builder`]
+    pub fn new(corp_id: String, corp_secret: String, interval: Option<Duration>) -> Client {
+        let mut builder = Client::builder(corp_id, corp_secret);
+        if let Some(interval) = interval {
+            builder = builder.interval(interval);
+        }
+        builder.build_lenient()
+    }
+
+    /// 创建Client构建器，可配置接口地址、超时、代理、User-Agent等
+    pub fn builder(corp_id: impl Into<String>, corp_secret: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(corp_id.into(), corp_secret.into())
+    }
+
+    #[deprecated(note = "use `Client::builder(..).interval(..)` or `rate_limit(..)` instead")]
+    pub fn with_interval(&mut self, interval: Duration) {
+        Arc::make_mut(&mut self.inner).rate_limiter =
+            Arc::new(RateLimiter::new(RateLimit::every(interval)));
+    }
+
+    fn http_client(&self) -> Result<&reqwest::Client> {
+        self.inner
+            .http_client
+            .as_ref()
+            .map_err(|err| Error::HttpClientBuild(err.clone()))
+    }
+
+    /// 读取未过期的缓存token
+    async fn cached_access_token(&self) -> Result<Option<String>> {
+        Ok(self
+            .inner
+            .token_store
+            .get()
+            .await?
+            .filter(|x| !x.needs_refresh(self.inner.token_refresh_margin))
+            .map(|x| x.access_token))
+    }
+
+    /// 获取access_token，缓存的token即将过期时提前刷新
+    async fn access_token(&self) -> Result<String> {
+        if let Some(token) = self.cached_access_token().await? {
+            return Ok(token);
+        }
+
+        let generation = self.inner.token_generation.load(Ordering::Acquire);
+        let mut last_failure = self.inner.token_lock.lock().await;
+        // 等待锁期间其他任务已完成刷新：失败时直接返回该错误，避免每个等待的任务依次重试
+        if self.inner.token_generation.load(Ordering::Acquire) != generation {
+            if let Some(failure) = &*last_failure {
+                return Err(failure.to_error());
+            }
+        }
+        if let Some(token) = self.cached_access_token().await? {
+            return Ok(token);
+        }
+
+        let result = self
+            .inner
+            .token_store
+            .get_or_refresh(
+                self.inner.token_refresh_margin,
+                Box::pin(self.request_access_token()),
+            )
+            .await;
+        *last_failure = result.as_ref().err().map(TokenRefreshFailure::new);
+        self.inner.token_generation.fetch_add(1, Ordering::Release);
+        Ok(result?.access_token)
+    }
+
+    async fn request_access_token(&self) -> Result<Token> {
+        let mut attempt = 1;
+        loop {
+            match self.fetch_access_token().await {
+                Err(err) if self.inner.retry_policy.should_retry(&err, attempt, true) => {
+                    let delay = self.inner.retry_policy.delay(attempt);
+                    warn!("request access_token failed: {err}, retry {attempt} after {delay:?}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                resp => return resp,
+            }
+        }
+    }
+
+    async fn fetch_access_token(&self) -> Result<Token> {
+        let query_body = json!({
+            "corpid": self.inner.corp_id,
+            "corpsecret": self.inner.corp_secret,
+        });
+
+        let http_client = self.http_client()?;
+        self.inner.rate_limiter.acquire("/cgi-bin/gettoken").await;
+        let resp = do_http(
+            http_client,
+            self.inner.cassette.as_deref(),
+            Method::GET,
+            &format!("{}/cgi-bin/gettoken", self.inner.base_url),
+            None,
+            Some(query_body),
+            None,
+        )
+        .await?;
+
+        let resp: TokenResponse = parse_response("/cgi-bin/gettoken", resp)?;
+        Ok(Token::new(resp.access_token, resp.expires_in))
+    }
+
+    // http 请求，GET请求视为幂等请求，失败时按重试策略重试
+    async fn request<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<R> {
+        let idempotent = method == Method::GET;
+        self.execute(method, path, body, idempotent).await
+    }
+
+    // 幂等的POST请求，如查询、更新
+    async fn request_idempotent<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<R> {
+        self.execute(method, path, body, true).await
+    }
+
+    // 自动携带access_token，token失效时刷新后重试一次，其他临时错误按重试策略重试
+    async fn execute<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+        idempotent: bool,
+    ) -> Result<R> {
+        let mut token_retried = false;
+        let mut attempt = 1;
+        loop {
+            let token = self.access_token().await?;
+            self.inner.rate_limiter.acquire(path).await;
+            let err = match self
+                .do_request(method.clone(), path, &token, body.clone())
+                .await
+            {
+                Ok(resp) => return Ok(resp),
+                Err(err) => err,
+            };
+
+            match err.api_code() {
+                Some(code) if !token_retried && code.is_token_expired() => {
+                    debug!("access_token is invalid, errcode: {code}, retry with a new one");
+                    self.inner.token_store.invalidate(&token).await?;
+                    token_retried = true;
+                }
+                _ if self
+                    .inner
+                    .retry_policy
+                    .should_retry(&err, attempt, idempotent) =>
+                {
+                    let delay = self.inner.retry_policy.delay(attempt);
+                    warn!("request {path} failed: {err}, retry {attempt} after {delay:?}");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => return Err(err),
+            }
+        }
+    }
+
+    async fn do_request<R: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        token: &str,
+        body: Option<Value>,
+    ) -> Result<R> {
+        let body = body.map(PostParameters::json);
+        let resp = do_http(
+            self.http_client()?,
+            self.inner.cassette.as_deref(),
+            method,
+            &format!("{}{}", self.inner.base_url, path),
+            None,
+            Some(json!({ "access_token": token })),
+            body,
+        )
+        .await?;
+
+        parse_response(path, resp)
+    }
+}
+
+/// 刷新token失败的错误，共享给等待刷新的任务
+#[derive(Debug, Clone)]
+struct TokenRefreshFailure {
+    code: Option<ApiErrorCode>,
+    message: String,
+}
+
+impl TokenRefreshFailure {
+    fn new(err: &Error) -> Self {
+        TokenRefreshFailure {
+            code: err.api_code(),
+            message: err.to_string(),
+        }
+    }
+
+    fn to_error(&self) -> Error {
+        Error::TokenRefreshFailed {
+            code: self.code,
+            message: self.message.clone(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait Exmailer {
+    /// 创建部门，成功返回创建后的部门ID
+    async fn create_department(&self, params: ParamsCreateDepartment) -> Result<u64>;
+    /// 更新部门
+    async fn update_department(&self, params: ParamsUpdateDepartment) -> Result<()>;
+    /// 删除部门
+    async fn delete_department(&self, id: u64) -> Result<()>;
+    /// 获取部门列表， ID为1时获取根部门下的子部门
+    async fn list_department(&self, id: Option<u64>) -> Result<Vec<Department>>;
+    /// 查找部门
+    async fn search_department(&self, params: ParamsSerchDepartment) -> Result<Vec<Department>>;
+    /// 创建用户
+    async fn create_user(&self, params: ParamsCreateUser) -> Result<()>;
+    /// 更新用户
+    async fn update_user(&self, params: ParamsUpdateUser) -> Result<()>;
+    /// 删除用户
+    async fn delete_user(&self, user_id: &str) -> Result<()>;
+    /// 获取用户
+    async fn get_user(&self, user_id: &str) -> Result<User>;
+    /// 获取部门用户
+    async fn get_department_user(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<User>>;
+    /// 获取部门用户简要信息(userid、名称及所属部门)
+    async fn list_department_user_simple(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<UserSimple>>;
+    /// 批量检查账户
+    async fn batchcheck_user(&self, userids: &[&str]) -> Result<Vec<UserCheck>>;
+    /// 创建群组
+    async fn create_group(&self, params: ParamsCreateGroup) -> Result<()>;
+    /// 更新群组
+    async fn update_group(&self, params: ParamsUpdateGroup) -> Result<()>;
+    /// 删除群组
+    async fn delete_group(&self, group_id: &str) -> Result<()>;
+    /// 获取群组信息
+    async fn get_group(&self, group_id: &str) -> Result<Group>;
+    /// 获取成员功能属性，`types`为空时返回全部属性
+    async fn get_user_option(
+        &self,
+        user_id: &str,
+        types: &[UserOption],
+    ) -> Result<Vec<UserOptionValue>>;
+    /// 更新成员功能属性，未指定的属性保持不变
+    async fn update_user_option(&self, user_id: &str, options: &[UserOptionValue]) -> Result<()>;
+    /// 获取成员的单点登录链接
+    async fn get_login_url(&self, user_id: &str) -> Result<LoginUrl>;
+    /// 获取成员在日期范围内的未读邮件数
+    async fn get_new_mail_count(
+        &self,
+        user_id: &str,
+        begin_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<NewMailCount>;
+}
+
+#[async_trait]
+impl Exmailer for Client {
+    /// 参考接口说明：https://service.rtxmail.net/api/267.html
+    async fn create_department(&self, params: ParamsCreateDepartment) -> Result<u64> {
+        let resp = self
+            .request::<CreateDepartmentResponse>(
+                Method::POST,
+                "/cgi-bin/department/create",
+                Some(serde_json::to_value(params)?),
+            )
+            .await?;
+
+        Ok(resp.id)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/268.html
+    async fn update_department(&self, params: ParamsUpdateDepartment) -> Result<()> {
+        self.request_idempotent::<Empty>(
+            Method::POST,
+            "/cgi-bin/department/update",
+            Some(serde_json::to_value(params)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/269.html
+    async fn delete_department(&self, id: u64) -> Result<()> {
+        self.request::<Empty>(
+            Method::GET,
+            &format!("/cgi-bin/department/delete?id={id}"),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/270.html
+    async fn list_department(&self, id: Option<u64>) -> Result<Vec<Department>> {
+        let id = id.unwrap_or(1);
+
+        let resp: DepartmentListResponse = self
+            .request(
+                Method::GET,
+                &format!("/cgi-bin/department/list?id={id}"),
+                None,
+            )
+            .await?;
+
+        Ok(resp.department)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/271.html
+    async fn search_department(&self, params: ParamsSerchDepartment) -> Result<Vec<Department>> {
+        let resp: DepartmentListResponse = self
+            .request_idempotent(
+                Method::POST,
+                "/cgi-bin/department/search",
+                Some(serde_json::to_value(&params)?),
+            )
+            .await?;
+
+        Ok(resp.department)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/272.html
+    async fn create_user(&self, params: ParamsCreateUser) -> Result<()> {
+        self.request::<Empty>(
+            Method::POST,
+            "/cgi-bin/user/create",
+            Some(serde_json::to_value(params)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/273.html
+    async fn update_user(&self, params: ParamsUpdateUser) -> Result<()> {
+        self.request_idempotent::<Empty>(
+            Method::POST,
+            "/cgi-bin/user/update",
+            Some(serde_json::to_value(params)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/274.html
+    async fn delete_user(&self, user_id: &str) -> Result<()> {
+        self.request::<Empty>(
+            Method::GET,
+            &format!("/cgi-bin/user/delete?userid={user_id}"),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/275.html
+    async fn get_user(&self, user_id: &str) -> Result<User> {
+        let resp = self
+            .request::<User>(
+                Method::GET,
+                &format!("/cgi-bin/user/get?userid={user_id}"),
+                None,
+            )
+            .await?;
+
+        Ok(resp)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/276.html
+    async fn list_department_user_simple(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<UserSimple>> {
+        let fetch_child = fetch_child
+            .map(|x| if x { 1 } else { 0 })
+            .unwrap_or_default();
+        let resp = self
+            .request::<UserSimpleListResponse>(
+                Method::GET,
+                &format!(
+                    "/cgi-bin/user/simplelist?department_id={department_id}&fetch_child={fetch_child}"
+                ),
+                None,
+            )
+            .await?;
+
+        Ok(resp.user_list)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/277.html
+    async fn get_department_user(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<User>> {
+        let fetch_child = fetch_child
+            .map(|x| if x { 1 } else { 0 })
+            .unwrap_or_default();
+        let resp = self
+            .request::<UserListResponse>(
+                Method::GET,
+                &format!(
+                    "/cgi-bin/user/list?department_id={department_id}&fetch_child={fetch_child}"
+                ),
+                None,
+            )
+            .await?;
+
+        Ok(resp.user_list)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/278.html
+    async fn batchcheck_user(&self, userids: &[&str]) -> Result<Vec<UserCheck>> {
+        let resp = self
+            .request_idempotent::<BatchCheckResponse>(
+                Method::POST,
+                "/cgi-bin/user/batchcheck",
+                Some(serde_json::json!({
+                    "userlist": userids,
+                })),
+            )
+            .await?;
+        Ok(resp.list)
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/279.html
+    async fn create_group(&self, params: ParamsCreateGroup) -> Result<()> {
+        self.request::<Empty>(
+            Method::POST,
+            "/cgi-bin/group/create",
+            Some(serde_json::to_value(params)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/280.html
+    async fn update_group(&self, params: ParamsUpdateGroup) -> Result<()> {
+        self.request_idempotent::<Empty>(
+            Method::POST,
+            "/cgi-bin/group/update",
+            Some(serde_json::to_value(params)?),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/281.html
+    async fn delete_group(&self, group_id: &str) -> Result<()> {
+        self.request::<Empty>(
+            Method::GET,
+            &format!("/cgi-bin/group/delete?groupid={group_id}"),
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// 参考接口说明：https://service.rtxmail.net/api/282.html
+    async fn get_group(&self, group_id: &str) -> Result<Group> {
+        let resp = self
+            .request::<Group>(
+                Method::GET,
+                &format!("/cgi-bin/group/get?groupid={group_id}"),
+                None,
+            )
+            .await?;
+
+        Ok(resp)
+    }
+
+    async fn get_user_option(
+        &self,
+        user_id: &str,
+        types: &[UserOption],
+    ) -> Result<Vec<UserOptionValue>> {
+        let types = if types.is_empty() {
+            UserOption::ALL.as_slice()
+        } else {
+            types
+        };
+        let resp = self
+            .request_idempotent::<UserOptionResponse>(
+                Method::POST,
+                "/cgi-bin/useroption/get",
+                Some(serde_json::json!({
+                    "userid": user_id,
+                    "type": types,
+                })),
+            )
+            .await?;
+        Ok(resp.option)
+    }
+
+    async fn update_user_option(&self, user_id: &str, options: &[UserOptionValue]) -> Result<()> {
+        self.request_idempotent::<Empty>(
+            Method::POST,
+            "/cgi-bin/useroption/update",
+            Some(serde_json::json!({
+                "userid": user_id,
+                "option": options,
+            })),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_login_url(&self, user_id: &str) -> Result<LoginUrl> {
+        let resp = self
+            .request::<LoginUrl>(
+                Method::GET,
+                &format!("/cgi-bin/service/get_login_url?userid={user_id}"),
+                None,
+            )
+            .await?;
+
+        Ok(resp)
+    }
+
+    async fn get_new_mail_count(
+        &self,
+        user_id: &str,
+        begin_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<NewMailCount> {
+        let resp = self
+            .request::<NewMailCount>(
+                Method::GET,
+                &format!(
+                    "/cgi-bin/mail/newcount?userid={user_id}&begin_date={begin_date}&end_date={end_date}"
+                ),
+                None,
+            )
+            .await?;
+
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use serde::{Deserialize, Serialize};
+
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize, Serialize)]
+    struct Rr {
+        errcode: i64,
+        errmsg: String,
+        #[serde(rename = "userid")]
+        user_id: String,
+        name: String,
+        department: Vec<u64>,
+        position: String,
+        mobile: String,
+        gender: String,
+        enable: u8,
+        slaves: Vec<String>,
+        cpwd_login: Option<u8>,
+    }
+
+    #[test]
+    fn client_is_clone_send_sync() {
+        fn assert_impl<T: Clone + Send + Sync + 'static>() {}
+        assert_impl::<super::Client>();
+    }
+
+    #[test]
+    fn test() {
+        let json_str = r##"{
+            "errcode":0,
+            "errmsg":"ok",
+            "userid":"shenshouer2955@ipalfish.com",
+            "name":"沈首二",
+            "department":[7065571265906742219,6726112443993748595],
+            "position":"",
+            "mobile":"",
+            "tel":"",
+            "extid":"",
+            "gender":"1",
+            "slaves":[],
+            "enable":1
+        }"##;
+
+        // let resp = serde_json::from_str::<Rr>(json_str);
+        let resp = serde_json::from_str::<serde_json::Value>(json_str);
+        println!("{:?}", resp);
+        if let Ok(v) = resp {
+            let r = serde_json::from_value::<super::User>(v);
+            println!("{:?}", r);
+        }
+    }
+}
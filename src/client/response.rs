@@ -0,0 +1,134 @@
+//! 各接口的响应数据
+
+use crate::{
+    errs::{new_api_error, new_unexpected_response, Result},
+    models::*,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+
+/// 所有接口共有的返回码
+#[derive(Debug, Deserialize)]
+struct Status {
+    #[serde(rename = "errcode", default)]
+    error_code: i64,
+    #[serde(rename = "errmsg", default)]
+    error_message: String,
+}
+
+/// 解析响应：errcode非0时返回接口错误，缺少必要字段时返回[`Error::UnexpectedResponse`](crate::errs::Error::UnexpectedResponse)
+pub(crate) fn parse_response<R: DeserializeOwned>(endpoint: &str, body: String) -> Result<R> {
+    let status = match serde_json::from_str::<Status>(&body) {
+        Ok(status) => status,
+        Err(_) => return Err(new_unexpected_response(endpoint, body)),
+    };
+    if status.error_code != 0 {
+        return Err(new_api_error(status.error_code, status.error_message));
+    }
+    serde_json::from_str(&body).map_err(|_| new_unexpected_response(endpoint, body))
+}
+
+/// 无数据返回
+#[derive(Debug, Deserialize)]
+pub(crate) struct Empty {}
+
+/// 获取access_token
+#[derive(Debug, Deserialize)]
+pub(crate) struct TokenResponse {
+    pub access_token: String,
+    pub expires_in: u64,
+}
+
+/// 创建部门
+#[derive(Debug, Deserialize)]
+pub(crate) struct CreateDepartmentResponse {
+    pub id: u64,
+}
+
+/// 部门列表、查找部门
+#[derive(Debug, Deserialize)]
+pub(crate) struct DepartmentListResponse {
+    pub department: Vec<Department>,
+}
+
+/// 部门成员
+#[derive(Debug, Deserialize)]
+pub(crate) struct UserListResponse {
+    #[serde(rename = "userlist")]
+    pub user_list: Vec<User>,
+}
+
+/// 部门成员简要信息
+#[derive(Debug, Deserialize)]
+pub(crate) struct UserSimpleListResponse {
+    #[serde(rename = "userlist")]
+    pub user_list: Vec<UserSimple>,
+}
+
+/// 批量检查帐号
+#[derive(Debug, Deserialize)]
+pub(crate) struct BatchCheckResponse {
+    pub list: Vec<UserCheck>,
+}
+
+/// 邮件日志
+#[derive(Debug, Deserialize)]
+pub(crate) struct MailLogResponse {
+    #[serde(default)]
+    pub list: Vec<MailLog>,
+}
+
+/// 登录日志
+#[derive(Debug, Deserialize)]
+pub(crate) struct LoginLogResponse {
+    #[serde(default)]
+    pub list: Vec<LoginLog>,
+}
+
+/// 批量任务日志
+#[derive(Debug, Deserialize)]
+pub(crate) struct BatchJobLogResponse {
+    #[serde(default)]
+    pub list: Vec<BatchJobLog>,
+}
+
+/// 操作日志
+#[derive(Debug, Deserialize)]
+pub(crate) struct OperationLogResponse {
+    #[serde(default)]
+    pub list: Vec<OperationLog>,
+}
+
+/// 获取成员功能属性
+#[derive(Debug, Deserialize)]
+pub(crate) struct UserOptionResponse {
+    #[serde(default)]
+    pub option: Vec<UserOptionValue>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errs::Error;
+
+    #[test]
+    fn missing_field_is_unexpected_response() {
+        let body = r#"{"errcode":0,"errmsg":"ok"}"#.to_owned();
+        let err = parse_response::<CreateDepartmentResponse>("/cgi-bin/department/create", body)
+            .unwrap_err();
+        match err {
+            Error::UnexpectedResponse { endpoint, body } => {
+                assert_eq!(endpoint, "/cgi-bin/department/create");
+                assert_eq!(body, r#"{"errcode":0,"errmsg":"ok"}"#);
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+
+        let body = r#"{"errcode":60003,"errmsg":"department not found"}"#.to_owned();
+        let err = parse_response::<Empty>("/cgi-bin/department/delete", body).unwrap_err();
+        assert!(err.is_not_found());
+
+        let body = r#"{"errcode":0,"errmsg":"ok","id":2}"#.to_owned();
+        let resp = parse_response::<CreateDepartmentResponse>("/cgi-bin/department/create", body);
+        assert_eq!(resp.unwrap().id, 2);
+    }
+}
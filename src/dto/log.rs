@@ -0,0 +1,94 @@
+pub use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// 查询邮件概况参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ParamsMailStatus {
+    /// 域名
+    pub domain: String,
+    /// 开始日期
+    pub begin_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+}
+
+crate::utils::serde::int_enum! {
+    /// 邮件类型
+    pub enum MailType: u8 {
+        /// 收信及发信
+        All = 0,
+        /// 发信
+        Sent = 1,
+        /// 收信
+        Received = 2,
+    }
+}
+
+/// 查询邮件日志参数
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ParamsMailLog {
+    /// 域名
+    pub domain: String,
+    /// 开始日期
+    pub begin_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+    /// 邮件类型
+    #[serde(rename = "mailtype")]
+    pub mail_type: MailType,
+    /// 只查询该成员的邮件
+    #[serde(rename = "userid", skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// 按邮件主题过滤
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+}
+
+/// 查询成员登录日志参数，日期范围超过接口限制时会自动分段查询
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParamsLoginLog {
+    /// 成员UserID
+    #[serde(rename = "userid")]
+    pub user_id: String,
+    /// 开始日期
+    pub begin_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+}
+
+/// 查询批量任务日志参数
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParamsBatchJobLog {
+    /// 开始日期
+    pub begin_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+}
+
+crate::utils::serde::int_enum! {
+    /// 操作类型
+    pub enum OperationType: u8 {
+        /// 全部操作，仅用于查询
+        All = 1,
+        /// 成员及部门管理
+        Account = 2,
+        /// 邮件群组管理
+        Group = 3,
+        /// 企业及域名设置
+        Setting = 4,
+        /// 管理员及权限
+        Admin = 5,
+    }
+}
+
+/// 查询操作日志参数
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParamsOperationLog {
+    /// 操作类型
+    #[serde(rename = "type")]
+    pub operation_type: OperationType,
+    /// 开始日期
+    pub begin_date: NaiveDate,
+    /// 结束日期
+    pub end_date: NaiveDate,
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::tabs_in_doc_comments)]
+
 use serde::{Deserialize, Serialize};
 
 /// 创建部门参数
@@ -143,8 +145,7 @@ pub struct ParamsCreateGroup {
 }
 
 /// 更新群组参数
-
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ParamsUpdateGroup {
     /// 是	邮件群组名称
     pub groupid: String,
@@ -161,8 +162,12 @@ pub struct ParamsUpdateGroup {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub department: Option<Vec<u64>>,
     ///	是	群发权限。0: 企业成员, 1任何人， 2:组内成员，3:指定成员
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_type: Option<u8>,
     /// 否	群发权限为指定成员时，需要指定成员
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_userlist: Option<String>,
 }
+
+mod log;
+pub use log::*;
@@ -1,4 +1,5 @@
 use reqwest::StatusCode;
+use std::{fmt, sync::Arc};
 use thiserror::Error;
 
 pub type Result<T> = core::result::Result<T, Error>;
@@ -14,7 +15,34 @@ pub enum Error {
     },
     // API error
     #[error("errcode: {code}, errmsg: {message}")]
-    ApiError { code: u64, message: String },
+    ApiError { code: ApiErrorCode, message: String },
+    // 返回码正常但响应数据不符合预期
+    #[error("unexpected response from {endpoint}: {body}")]
+    UnexpectedResponse { endpoint: String, body: String },
+    // 调用参数不合法，未发送请求
+    #[error("invalid argument: {message}")]
+    InvalidArgument { message: String },
+    // 读取-修改-写入期间数据被其他操作修改
+    #[error("concurrent modification detected on {resource}")]
+    Conflict { resource: String },
+    // 回调消息签名校验失败
+    #[error("invalid callback signature")]
+    InvalidSignature,
+    // 回调消息解密失败或格式错误
+    #[error("invalid callback message: {message}")]
+    InvalidCallbackMessage { message: String },
+    // 等待期间其他任务刷新access_token失败，共享该错误而不重复请求
+    #[error("refresh access_token failed: {message}")]
+    TokenRefreshFailed {
+        code: Option<ApiErrorCode>,
+        message: String,
+    },
+    // 创建Client时http client初始化失败，如PROXY环境变量不合法
+    #[error("build http client failed: {0}")]
+    HttpClientBuild(Arc<reqwest::Error>),
+    // 回放时没有匹配的录制记录
+    #[error("no recorded interaction for {method} {url}")]
+    CassetteMiss { method: String, url: String },
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
     #[error(transparent)]
@@ -23,6 +51,8 @@ pub enum Error {
     InvalidHeaderName(#[from] reqwest::header::InvalidHeaderName),
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
 
 pub fn new_http_error(url: String, status_code: StatusCode, message: String) -> Error {
@@ -33,6 +63,213 @@ pub fn new_http_error(url: String, status_code: StatusCode, message: String) ->
     }
 }
 
-pub fn new_api_error(code: u64, message: String) -> Error {
-    Error::ApiError { code, message }
+pub fn new_api_error(code: i64, message: String) -> Error {
+    Error::ApiError {
+        code: code.into(),
+        message,
+    }
+}
+
+pub fn new_unexpected_response(endpoint: &str, body: String) -> Error {
+    Error::UnexpectedResponse {
+        endpoint: endpoint.split('?').next().unwrap_or_default().to_owned(),
+        body,
+    }
+}
+
+pub fn new_invalid_argument(message: impl Into<String>) -> Error {
+    Error::InvalidArgument {
+        message: message.into(),
+    }
+}
+
+pub fn new_conflict(resource: impl Into<String>) -> Error {
+    Error::Conflict {
+        resource: resource.into(),
+    }
+}
+
+pub fn new_invalid_callback_message(message: impl Into<String>) -> Error {
+    Error::InvalidCallbackMessage {
+        message: message.into(),
+    }
+}
+
+impl Error {
+    /// 接口返回的错误码，非接口错误时返回`None`
+    pub fn api_code(&self) -> Option<ApiErrorCode> {
+        match self {
+            Error::ApiError { code, .. } => Some(*code),
+            Error::TokenRefreshFailed { code, .. } => *code,
+            _ => None,
+        }
+    }
+
+    /// 请求的成员、部门等不存在
+    pub fn is_not_found(&self) -> bool {
+        self.api_code().is_some_and(|c| c.is_not_found())
+    }
+
+    /// 接口繁忙或频率超限，稍后重试可能成功
+    pub fn is_retryable(&self) -> bool {
+        self.api_code().is_some_and(|c| c.is_retryable())
+    }
+
+    /// corpid、secret或access_token错误
+    pub fn is_auth_error(&self) -> bool {
+        self.api_code().is_some_and(|c| c.is_auth_error())
+    }
+
+    /// 读取-修改-写入期间数据被并发修改
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, Error::Conflict { .. })
+    }
+}
+
+macro_rules! api_error_codes {
+    ($($(#[$doc:meta])* $name:ident = $code:literal,)*) => {
+        /// 腾讯企业邮箱全局返回码
+        ///
+        /// 未收录的返回码保存在[`ApiErrorCode::Unknown`]中
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum ApiErrorCode {
+            $($(#[$doc])* $name,)*
+            /// 未收录的返回码
+            Unknown(i64),
+        }
+
+        impl ApiErrorCode {
+            /// 原始返回码
+            pub fn code(&self) -> i64 {
+                match self {
+                    $(ApiErrorCode::$name => $code,)*
+                    ApiErrorCode::Unknown(code) => *code,
+                }
+            }
+        }
+
+        impl From<i64> for ApiErrorCode {
+            fn from(code: i64) -> Self {
+                match code {
+                    $($code => ApiErrorCode::$name,)*
+                    code => ApiErrorCode::Unknown(code),
+                }
+            }
+        }
+    };
+}
+
+api_error_codes! {
+    /// 系统繁忙
+    SystemBusy = -1,
+    /// 获取access_token时CorpSecret错误，或者access_token无效
+    InvalidSecret = 40001,
+    /// 不合法的UserID
+    InvalidUserId = 40003,
+    /// 不合法的corpid
+    InvalidCorpId = 40013,
+    /// 不合法的access_token
+    InvalidAccessToken = 40014,
+    /// access_token已过期
+    AccessTokenExpired = 42001,
+    /// 接口调用超过频率限制
+    RateLimited = 45009,
+    /// 企业已禁用
+    CorpDisabled = 50005,
+    /// 部门不存在
+    DepartmentNotFound = 60003,
+    /// 父部门不存在
+    ParentDepartmentNotFound = 60004,
+    /// 不允许删除有成员的部门
+    DepartmentHasUsers = 60005,
+    /// 不允许删除有子部门的部门
+    DepartmentHasChildren = 60006,
+    /// 不允许删除根部门
+    DeleteRootDepartment = 60007,
+    /// 部门名称已存在
+    DepartmentNameExists = 60008,
+    /// 部门名称含有非法字符
+    InvalidDepartmentName = 60009,
+    /// 部门存在循环关系
+    DepartmentCycle = 60010,
+    /// UserID已存在
+    UserIdExists = 60102,
+    /// UserID不存在
+    UserNotFound = 60111,
+    /// 成员姓名不合法
+    InvalidUserName = 60112,
+    /// 无效的部门id
+    InvalidDepartmentId = 60123,
+    /// 字段不合法，如超出数量限制或格式错误
+    InvalidField = 60128,
+}
+
+impl ApiErrorCode {
+    /// 成员、部门不存在
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            ApiErrorCode::UserNotFound
+                | ApiErrorCode::DepartmentNotFound
+                | ApiErrorCode::ParentDepartmentNotFound
+        )
+    }
+
+    /// 部门非空，不允许删除
+    pub fn is_department_not_empty(&self) -> bool {
+        matches!(
+            self,
+            ApiErrorCode::DepartmentHasUsers | ApiErrorCode::DepartmentHasChildren
+        )
+    }
+
+    /// 接口繁忙或频率超限，稍后重试可能成功
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, ApiErrorCode::SystemBusy | ApiErrorCode::RateLimited)
+    }
+
+    /// corpid、secret或access_token错误
+    pub fn is_auth_error(&self) -> bool {
+        matches!(
+            self,
+            ApiErrorCode::InvalidSecret
+                | ApiErrorCode::InvalidCorpId
+                | ApiErrorCode::InvalidAccessToken
+                | ApiErrorCode::AccessTokenExpired
+        )
+    }
+
+    /// access_token无效或过期，刷新token后可重试
+    pub fn is_token_expired(&self) -> bool {
+        matches!(
+            self,
+            ApiErrorCode::InvalidAccessToken | ApiErrorCode::AccessTokenExpired
+        )
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_error_code_round_trip() {
+        assert_eq!(ApiErrorCode::from(60111), ApiErrorCode::UserNotFound);
+        assert_eq!(ApiErrorCode::from(-1), ApiErrorCode::SystemBusy);
+        assert_eq!(ApiErrorCode::from(12345), ApiErrorCode::Unknown(12345));
+        assert_eq!(ApiErrorCode::Unknown(12345).code(), 12345);
+        assert_eq!(ApiErrorCode::AccessTokenExpired.code(), 42001);
+
+        let err = new_api_error(60111, "userid not found".to_owned());
+        assert!(err.is_not_found());
+        assert!(!err.is_retryable());
+        assert_eq!(err.to_string(), "errcode: 60111, errmsg: userid not found");
+    }
 }
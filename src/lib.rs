@@ -3,6 +3,17 @@ pub mod errs;
 pub mod client;
 pub use client::Client;
 
+#[cfg(feature = "callback")]
+pub mod callback;
+
+pub mod cassette;
+pub mod ratelimit;
+pub mod retry;
+pub mod token;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
 pub(crate) mod utils;
 
 /// 参数数据转换层
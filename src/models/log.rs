@@ -0,0 +1,146 @@
+use crate::dto::{MailType, OperationType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 邮件概况
+///
+/// `/cgi-bin/log/mailstatus`只返回发信总量`sendsum`及收信总量`recvsum`，不包含失败数量。
+/// 失败的邮件需通过[`list_mail_log`](crate::client::LogApi::list_mail_log)按[`MailLogStatus`]统计
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct MailStatus {
+    /// 发信总量
+    #[serde(rename = "sendsum")]
+    pub sent: u64,
+    /// 收信总量
+    #[serde(rename = "recvsum")]
+    pub received: u64,
+}
+
+crate::utils::serde::int_enum! {
+    /// 邮件投递状态
+    pub enum MailLogStatus: u8 {
+        /// 其他状态
+        Other = 0,
+        /// 发信中
+        Sending = 1,
+        /// 被退信
+        Bounced = 2,
+        /// 发信成功
+        Sent = 3,
+        /// 发信失败
+        SendFailed = 4,
+        /// 收信被拦截
+        Blocked = 11,
+        /// 收信，邮件进入垃圾箱
+        Spam = 12,
+        /// 收信成功，邮件在收件箱
+        Inbox = 13,
+        /// 收信成功，邮件在个人文件夹
+        Folder = 14,
+    }
+}
+
+impl MailLogStatus {
+    /// 是否已成功投递
+    pub fn is_delivered(&self) -> bool {
+        matches!(
+            self,
+            MailLogStatus::Sent
+                | MailLogStatus::Spam
+                | MailLogStatus::Inbox
+                | MailLogStatus::Folder
+        )
+    }
+}
+
+/// 邮件日志
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MailLog {
+    /// 邮件类型
+    #[serde(rename = "mailtype")]
+    pub mail_type: MailType,
+    /// 发信人
+    pub sender: String,
+    /// 收信人
+    pub receiver: String,
+    /// 邮件主题
+    #[serde(default)]
+    pub subject: String,
+    /// 时间
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub time: DateTime<Utc>,
+    /// 投递状态
+    pub status: MailLogStatus,
+}
+
+crate::utils::serde::int_enum! {
+    /// 登录类型
+    pub enum LoginType: u8 {
+        /// 网页登录
+        Web = 1,
+        /// 手机登录
+        Mobile = 2,
+        /// QQ邮箱App登录
+        App = 3,
+        /// 客户端登录，包括IMAP、POP、SMTP及Exchange
+        Client = 4,
+        /// 其他方式登录
+        Other = 5,
+    }
+}
+
+/// 登录日志
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoginLog {
+    /// 登录时间
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub time: DateTime<Utc>,
+    /// 登录IP
+    pub ip: String,
+    /// 登录类型
+    #[serde(rename = "type")]
+    pub login_type: LoginType,
+}
+
+crate::utils::serde::int_enum! {
+    /// 批量任务类型
+    pub enum BatchJobType: u8 {
+        /// 批量导入成员
+        ImportUsers = 1,
+        /// 批量修改成员
+        UpdateUsers = 2,
+        /// 批量删除成员
+        DeleteUsers = 3,
+        /// 批量导入邮件群组
+        ImportGroups = 4,
+    }
+}
+
+/// 批量任务日志
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct BatchJobLog {
+    /// 操作时间
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub time: DateTime<Utc>,
+    /// 操作人
+    pub operator: String,
+    /// 任务类型
+    #[serde(rename = "type")]
+    pub job_type: BatchJobType,
+}
+
+/// 操作日志
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OperationLog {
+    /// 操作时间
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub time: DateTime<Utc>,
+    /// 操作人
+    pub operator: String,
+    /// 操作类型
+    #[serde(rename = "type")]
+    pub operation_type: OperationType,
+    /// 操作内容及对象
+    #[serde(default)]
+    pub operation: String,
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// 未读邮件数
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct NewMailCount {
+    /// 未读邮件数
+    pub count: u64,
+}
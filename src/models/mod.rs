@@ -1,3 +1,5 @@
+#![allow(clippy::tabs_in_doc_comments)]
+
 use serde::{Deserialize, Serialize};
 
 /// 部门
@@ -27,6 +29,16 @@ pub struct User {
     pub cpwd_login: Option<u8>,
 }
 
+/// 用户简要信息
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UserSimple {
+    #[serde(rename = "userid")]
+    pub user_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub department: Vec<u64>,
+}
+
 /// 用户检查数据
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UserCheck {
@@ -60,3 +72,18 @@ pub struct Group {
     /// 	群发权限为指定成员时，需要指定成员，否则赋值失效
     pub allow_userlist: Vec<String>,
 }
+
+mod log;
+pub use log::*;
+
+mod service;
+pub use service::*;
+
+mod mail;
+pub use mail::*;
+
+mod option;
+pub use option::*;
+
+mod tree;
+pub use tree::*;
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+crate::utils::serde::int_enum! {
+    /// 成员功能属性
+    pub enum UserOption: u8 {
+        /// 强制启用安全登录
+        ForceSecureLogin = 1,
+        /// IMAP/SMTP服务
+        ImapSmtp = 2,
+        /// POP/SMTP服务
+        PopSmtp = 3,
+        /// 是否启用安全登录
+        SecureLogin = 4,
+    }
+}
+
+impl UserOption {
+    /// 所有已收录的功能属性
+    pub const ALL: [UserOption; 4] = [
+        UserOption::ForceSecureLogin,
+        UserOption::ImapSmtp,
+        UserOption::PopSmtp,
+        UserOption::SecureLogin,
+    ];
+}
+
+/// 成员功能属性及其开关状态
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct UserOptionValue {
+    /// 功能属性
+    #[serde(rename = "type")]
+    pub option: UserOption,
+    /// 是否开启，接口中以`"0"`/`"1"`表示
+    #[serde(rename = "value", with = "crate::utils::serde::bool_string")]
+    pub enabled: bool,
+}
+
+impl UserOptionValue {
+    pub fn new(option: UserOption, enabled: bool) -> Self {
+        UserOptionValue { option, enabled }
+    }
+}
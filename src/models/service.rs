@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// 单点登录链接
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoginUrl {
+    /// 登录跳转链接
+    pub login_url: String,
+    /// 有效期，单位秒
+    pub expires_in: u64,
+    /// 获取链接的时间
+    #[serde(skip, default = "Instant::now")]
+    pub fetched_at: Instant,
+}
+
+impl LoginUrl {
+    /// 链接失效时间，`expires_in`过大超出[`Instant`]表示范围时取接近上限的时间
+    pub fn expires_at(&self) -> Instant {
+        let mut ttl = Duration::from_secs(self.expires_in);
+        loop {
+            if let Some(expires_at) = self.fetched_at.checked_add(ttl) {
+                return expires_at;
+            }
+            ttl /= 2;
+        }
+    }
+
+    /// 链接是否已失效
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.expires_at()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_url_expiry() {
+        let url: LoginUrl = serde_json::from_str(
+            r#"{"errcode":0,"errmsg":"ok","login_url":"https://exmail.qq.com/cgi-bin/login","expires_in":300}"#,
+        )
+        .unwrap();
+        assert!(!url.is_expired());
+        assert_eq!(url.expires_at() - url.fetched_at, Duration::from_secs(300));
+
+        let forever = LoginUrl {
+            expires_in: u64::MAX,
+            ..url.clone()
+        };
+        assert!(!forever.is_expired());
+
+        let expired = LoginUrl {
+            expires_in: 0,
+            ..url
+        };
+        assert!(expired.is_expired());
+    }
+}
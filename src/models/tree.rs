@@ -0,0 +1,327 @@
+use super::Department;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// 部门树
+///
+/// 由[`list_department`](crate::client::Exmailer::list_department)返回的扁平部门列表构建，
+/// 子部门按`order`、`id`排序。无法从根部门到达的部门不在树中，分别记录为孤立部门或循环部门
+#[derive(Debug, Clone)]
+pub struct DepartmentTree {
+    root: u64,
+    departments: HashMap<u64, Department>,
+    children: HashMap<u64, Vec<u64>>,
+    orphans: Vec<u64>,
+    cycles: Vec<Vec<u64>>,
+}
+
+impl DepartmentTree {
+    /// 以`root`为根构建部门树，`departments`中不包含`root`时返回`None`
+    pub fn new(root: u64, departments: impl IntoIterator<Item = Department>) -> Option<Self> {
+        let departments: HashMap<u64, Department> =
+            departments.into_iter().map(|d| (d.id, d)).collect();
+        if !departments.contains_key(&root) {
+            return None;
+        }
+
+        let mut children: HashMap<u64, Vec<u64>> = HashMap::new();
+        for d in departments.values().filter(|d| d.id != root) {
+            children.entry(d.parent_id).or_default().push(d.id);
+        }
+        for ids in children.values_mut() {
+            ids.sort_by_key(|id| (departments[id].order, *id));
+        }
+
+        let mut tree = DepartmentTree {
+            root,
+            departments,
+            children,
+            orphans: vec![],
+            cycles: vec![],
+        };
+        tree.detect_unreachable();
+        Some(tree)
+    }
+
+    /// 找出无法从根部门到达的部门：沿父部门向上最终不存在的为孤立部门，回到自身的为循环
+    fn detect_unreachable(&mut self) {
+        let reachable: HashSet<u64> = self.bfs_ids(self.root).into_iter().collect();
+        let mut unreachable: Vec<u64> = self
+            .departments
+            .keys()
+            .filter(|id| !reachable.contains(id))
+            .copied()
+            .collect();
+        unreachable.sort_unstable();
+
+        let mut seen = HashSet::new();
+        for &id in &unreachable {
+            let mut chain = vec![];
+            let mut current = id;
+            while self.departments.contains_key(&current)
+                && !reachable.contains(&current)
+                && seen.insert(current)
+            {
+                chain.push(current);
+                current = self.departments[&current].parent_id;
+            }
+            if let Some(start) = chain.iter().position(|&c| c == current) {
+                let mut cycle = chain[start..].to_vec();
+                cycle.sort_unstable();
+                self.cycles.push(cycle);
+            }
+        }
+        let in_cycle: HashSet<u64> = self.cycles.iter().flatten().copied().collect();
+        self.orphans = unreachable
+            .into_iter()
+            .filter(|id| !in_cycle.contains(id))
+            .collect();
+    }
+
+    fn bfs_ids(&self, start: u64) -> Vec<u64> {
+        let mut ids = vec![];
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            if !visited.insert(id) {
+                continue;
+            }
+            ids.push(id);
+            queue.extend(self.children.get(&id).into_iter().flatten());
+        }
+        ids
+    }
+
+    fn dfs_ids(&self, start: u64, post_order: bool) -> Vec<u64> {
+        let mut ids = vec![];
+        let mut visited = HashSet::new();
+        // (部门id, 子部门是否已入栈)
+        let mut stack = vec![(start, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if expanded {
+                ids.push(id);
+                continue;
+            }
+            if !visited.insert(id) {
+                continue;
+            }
+            if post_order {
+                stack.push((id, true));
+            } else {
+                ids.push(id);
+            }
+            let children = self.children.get(&id).into_iter().flatten().rev();
+            stack.extend(children.map(|&child| (child, false)));
+        }
+        ids
+    }
+
+    fn resolve(&self, ids: Vec<u64>) -> impl Iterator<Item = &Department> + '_ {
+        ids.into_iter().map(move |id| &self.departments[&id])
+    }
+
+    /// 根部门
+    pub fn root(&self) -> &Department {
+        &self.departments[&self.root]
+    }
+
+    /// 树中的部门数量
+    pub fn len(&self) -> usize {
+        self.departments.len() - self.orphans.len() - self.cycles.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 按id查找树中的部门
+    pub fn get(&self, id: u64) -> Option<&Department> {
+        self.departments.get(&id).filter(|_| self.contains(id))
+    }
+
+    /// 部门是否在树中
+    pub fn contains(&self, id: u64) -> bool {
+        self.departments.contains_key(&id)
+            && !self.orphans.contains(&id)
+            && !self.cycles.iter().any(|c| c.contains(&id))
+    }
+
+    /// 父部门，根部门返回`None`
+    pub fn parent(&self, id: u64) -> Option<&Department> {
+        if id == self.root {
+            return None;
+        }
+        self.get(id).and_then(|d| self.get(d.parent_id))
+    }
+
+    /// 直属子部门
+    pub fn children(&self, id: u64) -> impl Iterator<Item = &Department> + '_ {
+        let ids = if self.contains(id) {
+            self.children.get(&id).cloned().unwrap_or_default()
+        } else {
+            vec![]
+        };
+        self.resolve(ids)
+    }
+
+    /// 从父部门到根部门依次返回所有上级部门
+    pub fn ancestors(&self, id: u64) -> impl Iterator<Item = &Department> + '_ {
+        std::iter::successors(self.parent(id), move |d| self.parent(d.id))
+    }
+
+    /// 部门深度，根部门为0
+    pub fn depth(&self, id: u64) -> Option<usize> {
+        self.get(id).map(|_| self.ancestors(id).count())
+    }
+
+    /// 部门路径，由根部门以下各级名称以`/`连接，如`"Sales/East/Team1"`，根部门为空字符串
+    pub fn path(&self, id: u64) -> Option<String> {
+        let department = self.get(id)?;
+        if id == self.root {
+            return Some(String::new());
+        }
+        let mut names: Vec<&str> = self
+            .ancestors(id)
+            .filter(|d| d.id != self.root)
+            .map(|d| d.name.as_str())
+            .collect();
+        names.reverse();
+        names.push(&department.name);
+        Some(names.join("/"))
+    }
+
+    /// 按[`path`](Self::path)格式的路径查找部门，忽略首尾及重复的`/`
+    pub fn find_by_path(&self, path: &str) -> Option<&Department> {
+        let mut current = self.root();
+        for name in path.split('/').filter(|s| !s.is_empty()) {
+            current = self.children(current.id).find(|d| d.name == name)?;
+        }
+        Some(current)
+    }
+
+    /// 从根部门开始深度优先(先序)遍历
+    pub fn dfs(&self) -> impl Iterator<Item = &Department> + '_ {
+        self.resolve(self.dfs_ids(self.root, false))
+    }
+
+    /// 深度优先后序遍历，子部门先于父部门返回，适用于逐级删除
+    pub fn dfs_post_order(&self) -> impl Iterator<Item = &Department> + '_ {
+        self.resolve(self.dfs_ids(self.root, true))
+    }
+
+    /// 从根部门开始广度优先遍历
+    pub fn bfs(&self) -> impl Iterator<Item = &Department> + '_ {
+        self.resolve(self.bfs_ids(self.root))
+    }
+
+    /// 以`id`为根的子树
+    pub fn subtree(&self, id: u64) -> Option<DepartmentTree> {
+        self.get(id)?;
+        let departments = self
+            .bfs_ids(id)
+            .into_iter()
+            .map(|id| self.departments[&id].clone());
+        DepartmentTree::new(id, departments)
+    }
+
+    /// 孤立部门id：不在环中但无法从根部门到达，如父部门不存在
+    pub fn orphans(&self) -> &[u64] {
+        &self.orphans
+    }
+
+    /// 存在循环关系的部门id，每组为一个环
+    pub fn cycles(&self) -> &[Vec<u64>] {
+        &self.cycles
+    }
+
+    /// 没有孤立部门及循环关系
+    pub fn is_consistent(&self) -> bool {
+        self.orphans.is_empty() && self.cycles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn department(id: u64, parent_id: u64, name: &str, order: u32) -> Department {
+        Department {
+            id,
+            name: name.to_owned(),
+            parent_id,
+            order,
+            path: None,
+        }
+    }
+
+    fn tree() -> DepartmentTree {
+        DepartmentTree::new(
+            1,
+            vec![
+                department(1, 0, "企业", 0),
+                department(2, 1, "Sales", 2),
+                department(3, 1, "Dev", 1),
+                department(4, 2, "East", 0),
+                department(5, 4, "Team1", 0),
+                department(6, 2, "West", 1),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn ids<'a>(departments: impl Iterator<Item = &'a Department>) -> Vec<u64> {
+        departments.map(|d| d.id).collect()
+    }
+
+    #[test]
+    fn lookup_and_path() {
+        let tree = tree();
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.parent(5).unwrap().id, 4);
+        assert!(tree.parent(1).is_none());
+        assert_eq!(ids(tree.children(2)), [4, 6]);
+        assert_eq!(ids(tree.ancestors(5)), [4, 2, 1]);
+        assert_eq!(tree.depth(5), Some(3));
+        assert_eq!(tree.path(5).unwrap(), "Sales/East/Team1");
+        assert_eq!(tree.path(1).unwrap(), "");
+        assert_eq!(tree.find_by_path("/Sales/East/Team1").unwrap().id, 5);
+        assert!(tree.find_by_path("Sales/North").is_none());
+        assert!(tree.is_consistent());
+    }
+
+    #[test]
+    fn traversal_and_subtree() {
+        let tree = tree();
+        assert_eq!(ids(tree.dfs()), [1, 3, 2, 4, 5, 6]);
+        assert_eq!(ids(tree.dfs_post_order()), [3, 5, 4, 6, 2, 1]);
+        assert_eq!(ids(tree.bfs()), [1, 3, 2, 4, 6, 5]);
+
+        let sales = tree.subtree(2).unwrap();
+        assert_eq!(sales.root().name, "Sales");
+        assert_eq!(ids(sales.bfs()), [2, 4, 6, 5]);
+        assert_eq!(sales.path(5).unwrap(), "East/Team1");
+        assert!(tree.subtree(99).is_none());
+    }
+
+    #[test]
+    fn detect_orphans_and_cycles() {
+        let tree = DepartmentTree::new(
+            1,
+            vec![
+                department(1, 0, "企业", 0),
+                department(2, 1, "Sales", 0),
+                department(3, 99, "Lost", 0),
+                department(4, 3, "LostChild", 0),
+                department(5, 6, "A", 0),
+                department(6, 5, "B", 0),
+            ],
+        )
+        .unwrap();
+        assert_eq!(tree.orphans(), [3, 4]);
+        assert_eq!(tree.cycles(), [vec![5, 6]]);
+        assert!(!tree.is_consistent());
+        assert_eq!(tree.len(), 2);
+        assert!(tree.get(5).is_none());
+        assert_eq!(ids(tree.dfs()), [1, 2]);
+        assert!(DepartmentTree::new(7, vec![department(1, 0, "企业", 0)]).is_none());
+    }
+}
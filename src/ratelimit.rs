@@ -0,0 +1,168 @@
+//! 令牌桶限流
+//!
+//! 同一[`Client`](crate::Client)及其clone的所有请求共用一个[`RateLimiter`]，也可通过
+//! [`ClientBuilder::rate_limiter`](crate::client::ClientBuilder::rate_limiter)在多个Client之间共享。
+//! 并发任务之间按令牌桶协调请求频率，流量低时不会额外等待。
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+use tokio::time::Instant;
+
+/// 限流配置：每`per`时间内最多`requests`个请求，允许瞬时突发`burst`个请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// 每秒最多`requests`个请求
+    pub fn per_second(requests: u32) -> Self {
+        RateLimit {
+            requests,
+            per: Duration::from_secs(1),
+            burst: requests,
+        }
+    }
+
+    /// 每分钟最多`requests`个请求
+    pub fn per_minute(requests: u32) -> Self {
+        RateLimit {
+            requests,
+            per: Duration::from_secs(60),
+            burst: requests,
+        }
+    }
+
+    /// 每隔`interval`一个请求，不允许突发
+    pub fn every(interval: Duration) -> Self {
+        RateLimit {
+            requests: 1,
+            per: interval,
+            burst: 1,
+        }
+    }
+
+    /// 设置突发请求数
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+/// 令牌桶
+#[derive(Debug)]
+struct Bucket {
+    /// 每秒生成的令牌数
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        let capacity = f64::from(limit.burst.max(1));
+        Bucket {
+            rate: f64::from(limit.requests.max(1)) / limit.per.as_secs_f64().max(f64::EPSILON),
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                updated_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// 预占一个令牌，返回需要等待的时间。令牌不足时允许透支，后到的请求排在后面等待
+    fn reserve(&self) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.updated_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity) - 1.0;
+        state.updated_at = now;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        }
+    }
+}
+
+/// 限流器，包含全局令牌桶及可选的按接口令牌桶
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    global: Option<Bucket>,
+    endpoints: HashMap<String, Bucket>,
+}
+
+impl RateLimiter {
+    /// 所有接口共用`limit`
+    pub fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            global: Some(Bucket::new(limit)),
+            endpoints: HashMap::new(),
+        }
+    }
+
+    /// 不限流
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// 为指定接口(如`/cgi-bin/user/get`)单独限流，请求需同时满足全局及接口的限流
+    pub fn with_endpoint(mut self, path: impl Into<String>, limit: RateLimit) -> Self {
+        self.endpoints.insert(path.into(), Bucket::new(limit));
+        self
+    }
+
+    /// 等待直到允许请求`path`，`path`中的查询参数会被忽略
+    pub async fn acquire(&self, path: &str) {
+        let path = path.split('?').next().unwrap_or_default();
+        let wait = [self.global.as_ref(), self.endpoints.get(path)]
+            .into_iter()
+            .flatten()
+            .map(Bucket::reserve)
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn burst_then_throttle() {
+        let limiter = RateLimiter::new(RateLimit::per_second(2));
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire("/cgi-bin/user/get?userid=a").await;
+        }
+        // 前2个请求为突发，之后每500ms一个
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn endpoint_bucket_is_independent() {
+        let limiter = RateLimiter::unlimited().with_endpoint(
+            "/cgi-bin/user/get",
+            RateLimit::every(Duration::from_secs(1)),
+        );
+        let start = Instant::now();
+        limiter.acquire("/cgi-bin/user/list").await;
+        limiter.acquire("/cgi-bin/user/list").await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire("/cgi-bin/user/get?userid=a").await;
+        limiter.acquire("/cgi-bin/user/get?userid=b").await;
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+}
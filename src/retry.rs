@@ -0,0 +1,142 @@
+//! 请求重试策略
+
+use crate::errs::Error;
+use std::time::Duration;
+
+/// 可重试的错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryOn {
+    /// http状态码为5xx或429
+    pub server_error: bool,
+    /// 连接失败或请求超时
+    pub network: bool,
+    /// 接口返回系统繁忙(-1)或调用频率超限(45009)
+    pub busy: bool,
+}
+
+impl Default for RetryOn {
+    fn default() -> Self {
+        RetryOn {
+            server_error: true,
+            network: true,
+            busy: true,
+        }
+    }
+}
+
+/// 重试策略，按指数退避计算重试间隔。
+///
+/// 默认只重试幂等请求(查询、更新、删除)，创建类请求重试可能导致重复创建，
+/// 需要时可设置`retry_non_idempotent`
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 最大尝试次数，包含首次请求。为1时不重试
+    pub max_attempts: u32,
+    /// 首次重试间隔，之后每次翻倍
+    pub base_delay: Duration,
+    /// 最大重试间隔
+    pub max_delay: Duration,
+    /// 是否在重试间隔上增加随机抖动，避免并发请求同时重试
+    pub jitter: bool,
+    /// 可重试的错误类型
+    pub retry_on: RetryOn,
+    /// 是否重试非幂等请求
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_on: RetryOn::default(),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// 第`attempt`次请求失败后是否重试，`attempt`从1开始
+    pub fn should_retry(&self, err: &Error, attempt: u32, idempotent: bool) -> bool {
+        if attempt >= self.max_attempts || !(idempotent || self.retry_non_idempotent) {
+            return false;
+        }
+        match err {
+            Error::HttpError { status_code, .. } => {
+                self.retry_on.server_error
+                    && (status_code.is_server_error() || status_code.as_u16() == 429)
+            }
+            Error::Reqwest(e) => self.retry_on.network && (e.is_timeout() || e.is_connect()),
+            Error::ApiError { code, .. } => self.retry_on.busy && code.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// 第`attempt`次请求失败后的重试间隔
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(31);
+        let delay = self.base_delay.saturating_mul(1 << exp).min(self.max_delay);
+        if self.jitter {
+            // 在[delay/2, delay]之间随机
+            let half = delay / 2;
+            half + half.mul_f64(fastrand::f64())
+        } else {
+            delay
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errs::{new_api_error, new_http_error};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn delay_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            jitter: false,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(350),
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+        assert_eq!(policy.delay(100), Duration::from_millis(350));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        let delay = policy.delay(2);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn retries_only_transient_errors() {
+        let policy = RetryPolicy::default();
+        let busy = new_api_error(-1, "system busy".to_owned());
+        let not_found = new_api_error(60111, "userid not found".to_owned());
+        let bad_gateway = new_http_error("u".to_owned(), StatusCode::BAD_GATEWAY, String::new());
+        let bad_request = new_http_error("u".to_owned(), StatusCode::BAD_REQUEST, String::new());
+
+        assert!(policy.should_retry(&busy, 1, true));
+        assert!(policy.should_retry(&bad_gateway, 2, true));
+        assert!(!policy.should_retry(&busy, 3, true));
+        assert!(!policy.should_retry(&busy, 1, false));
+        assert!(!policy.should_retry(&not_found, 1, true));
+        assert!(!policy.should_retry(&bad_request, 1, true));
+        assert!(!RetryPolicy::none().should_retry(&busy, 1, true));
+    }
+}
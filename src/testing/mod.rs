@@ -0,0 +1,762 @@
+//! 测试辅助
+//!
+//! [`FakeExmail`]在内存中实现了完整的[`Exmailer`]，按真实接口的规则校验参数并返回相同的错误码，
+//! 下游服务可以在单元测试中用它代替[`Client`](crate::Client)。
+//!
+//! ```
+//! use rtxmail::{client::*, testing::FakeExmail};
+//!
+//! # async fn run() -> rtxmail::errs::Result<()> {
+//! let fake = FakeExmail::new();
+//! let id = fake
+//!     .create_department(ParamsCreateDepartment {
+//!         name: "研发部".to_owned(),
+//!         parent_id: 1,
+//!         order: None,
+//!     })
+//!     .await?;
+//! assert!(fake.delete_department(1).await.is_err());
+//! fake.delete_department(id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "mock-server")]
+pub mod server;
+
+use crate::{
+    client::Exmailer,
+    dto::*,
+    errs::{new_api_error, ApiErrorCode, Result},
+    models::*,
+};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Instant,
+};
+
+/// 根部门id
+pub const ROOT_DEPARTMENT_ID: u64 = 1;
+/// 成员所属部门数上限
+pub const MAX_USER_DEPARTMENTS: usize = 20;
+/// 成员别名数上限
+pub const MAX_USER_SLAVES: usize = 5;
+/// 名称长度上限，单位字节
+const MAX_NAME_LEN: usize = 64;
+
+fn api_error<T>(code: ApiErrorCode, message: &str) -> Result<T> {
+    Err(new_api_error(code.code(), message.to_owned()))
+}
+
+/// 是否为邮箱格式
+fn is_email(s: &str) -> bool {
+    match s.split_once('@') {
+        Some((name, domain)) => {
+            !name.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !s.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+/// 成员及其未在[`User`]中返回的数据
+#[derive(Debug, Clone)]
+struct UserRecord {
+    user: User,
+    options: HashMap<UserOption, bool>,
+    new_mail_count: u64,
+}
+
+/// 内存中的通讯录：部门、成员及邮件群组
+///
+/// 方法与[`Exmailer`]一一对应，同步执行并返回与真实接口相同的错误码。
+/// 成员、别名及邮件群组共用同一个帐号空间，群组不存在时返回`60111`
+#[derive(Debug, Clone)]
+pub struct Directory {
+    departments: BTreeMap<u64, Department>,
+    users: BTreeMap<String, UserRecord>,
+    groups: BTreeMap<String, Group>,
+    next_department_id: u64,
+}
+
+impl Default for Directory {
+    fn default() -> Self {
+        Directory::new("企业")
+    }
+}
+
+impl Directory {
+    /// 只包含根部门的通讯录
+    pub fn new(root_name: &str) -> Self {
+        let root = Department {
+            id: ROOT_DEPARTMENT_ID,
+            name: root_name.to_owned(),
+            parent_id: 0,
+            order: 0,
+            path: None,
+        };
+        Directory {
+            departments: BTreeMap::from([(ROOT_DEPARTMENT_ID, root)]),
+            users: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            next_department_id: ROOT_DEPARTMENT_ID + 1,
+        }
+    }
+
+    /// 设置成员的未读邮件数
+    pub fn set_new_mail_count(&mut self, user_id: &str, count: u64) -> Result<()> {
+        self.user_mut(user_id)?.new_mail_count = count;
+        Ok(())
+    }
+
+    /// 帐号的类型：0未占用，1成员，2别名，3邮件群组
+    fn account_kind(&self, account: &str) -> u8 {
+        if self.users.contains_key(account) {
+            1
+        } else if self
+            .users
+            .values()
+            .any(|r| r.user.slaves.iter().any(|s| s == account))
+        {
+            2
+        } else if self.groups.contains_key(account) {
+            3
+        } else {
+            0
+        }
+    }
+
+    fn user_mut(&mut self, user_id: &str) -> Result<&mut UserRecord> {
+        match self.users.get_mut(user_id) {
+            Some(record) => Ok(record),
+            None => api_error(ApiErrorCode::UserNotFound, "userid not found"),
+        }
+    }
+
+    fn department(&self, id: u64) -> Result<&Department> {
+        match self.departments.get(&id) {
+            Some(department) => Ok(department),
+            None => api_error(ApiErrorCode::DepartmentNotFound, "department not found"),
+        }
+    }
+
+    /// `id`及其所有子孙部门
+    fn subtree(&self, id: u64) -> Vec<u64> {
+        let mut ids = vec![id];
+        let mut i = 0;
+        while i < ids.len() {
+            let parent = ids[i];
+            ids.extend(
+                self.departments
+                    .values()
+                    .filter(|d| d.parent_id == parent && d.id != parent)
+                    .map(|d| d.id),
+            );
+            i += 1;
+        }
+        ids
+    }
+
+    fn check_department_name(&self, name: &str, parent_id: u64, id: Option<u64>) -> Result<()> {
+        if name.trim().is_empty()
+            || name.len() > MAX_NAME_LEN
+            || name.contains(['\\', '/', ':', '*', '?', '"', '<', '>', '|'])
+        {
+            return api_error(
+                ApiErrorCode::InvalidDepartmentName,
+                "invalid department name",
+            );
+        }
+        if self
+            .departments
+            .values()
+            .any(|d| d.parent_id == parent_id && d.name == name && Some(d.id) != id)
+        {
+            return api_error(
+                ApiErrorCode::DepartmentNameExists,
+                "department name existed",
+            );
+        }
+        Ok(())
+    }
+
+    fn check_user_departments(&self, department: &[u64]) -> Result<()> {
+        if department.is_empty() || department.len() > MAX_USER_DEPARTMENTS {
+            return api_error(ApiErrorCode::InvalidField, "invalid department count");
+        }
+        if department
+            .iter()
+            .any(|id| !self.departments.contains_key(id))
+        {
+            return api_error(ApiErrorCode::InvalidDepartmentId, "invalid department id");
+        }
+        Ok(())
+    }
+
+    fn check_user_name(name: &str) -> Result<()> {
+        if name.trim().is_empty() || name.len() > MAX_NAME_LEN {
+            return api_error(ApiErrorCode::InvalidUserName, "invalid name");
+        }
+        Ok(())
+    }
+
+    fn check_slaves(&self, user_id: &str, slaves: &[String]) -> Result<()> {
+        if slaves.len() > MAX_USER_SLAVES || slaves.iter().any(|s| !is_email(s)) {
+            return api_error(ApiErrorCode::InvalidField, "invalid slaves");
+        }
+        for slave in slaves {
+            let taken = match self.account_kind(slave) {
+                0 => false,
+                2 => self
+                    .users
+                    .get(user_id)
+                    .is_none_or(|r| !r.user.slaves.contains(slave)),
+                _ => true,
+            };
+            if taken || slave == user_id {
+                return api_error(ApiErrorCode::UserIdExists, "slave existed");
+            }
+        }
+        Ok(())
+    }
+
+    fn check_group_members(
+        &self,
+        group_id: &str,
+        userlist: &[String],
+        grouplist: &[String],
+        department: &[u64],
+    ) -> Result<()> {
+        if userlist.iter().any(|u| !self.users.contains_key(u)) {
+            return api_error(ApiErrorCode::UserNotFound, "userlist not found");
+        }
+        if grouplist
+            .iter()
+            .any(|g| g == group_id || !self.groups.contains_key(g))
+        {
+            return api_error(ApiErrorCode::InvalidField, "invalid grouplist");
+        }
+        if department
+            .iter()
+            .any(|id| !self.departments.contains_key(id))
+        {
+            return api_error(ApiErrorCode::InvalidDepartmentId, "invalid department id");
+        }
+        Ok(())
+    }
+
+    pub fn create_department(&mut self, params: ParamsCreateDepartment) -> Result<u64> {
+        if !self.departments.contains_key(&params.parent_id) {
+            return api_error(
+                ApiErrorCode::ParentDepartmentNotFound,
+                "parent department not found",
+            );
+        }
+        self.check_department_name(&params.name, params.parent_id, None)?;
+
+        let id = self.next_department_id;
+        self.next_department_id += 1;
+        self.departments.insert(
+            id,
+            Department {
+                id,
+                name: params.name,
+                parent_id: params.parent_id,
+                order: params.order.unwrap_or_default(),
+                path: None,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn update_department(&mut self, params: ParamsUpdateDepartment) -> Result<()> {
+        let current = self.department(params.id)?.clone();
+        let parent_id = params.parent_id.unwrap_or(current.parent_id);
+        if parent_id != current.parent_id {
+            if params.id == ROOT_DEPARTMENT_ID {
+                return api_error(
+                    ApiErrorCode::DepartmentCycle,
+                    "root department can not move",
+                );
+            }
+            if !self.departments.contains_key(&parent_id) {
+                return api_error(
+                    ApiErrorCode::ParentDepartmentNotFound,
+                    "parent department not found",
+                );
+            }
+            if self.subtree(params.id).contains(&parent_id) {
+                return api_error(ApiErrorCode::DepartmentCycle, "department cycle");
+            }
+        }
+        let name = params.name.unwrap_or(current.name);
+        self.check_department_name(&name, parent_id, Some(params.id))?;
+
+        let department = self.departments.get_mut(&params.id).unwrap();
+        department.name = name;
+        department.parent_id = parent_id;
+        if let Some(order) = params.order {
+            department.order = order;
+        }
+        Ok(())
+    }
+
+    pub fn delete_department(&mut self, id: u64) -> Result<()> {
+        if id == ROOT_DEPARTMENT_ID {
+            return api_error(
+                ApiErrorCode::DeleteRootDepartment,
+                "can not delete root department",
+            );
+        }
+        self.department(id)?;
+        if self.departments.values().any(|d| d.parent_id == id) {
+            return api_error(
+                ApiErrorCode::DepartmentHasChildren,
+                "department has children",
+            );
+        }
+        if self.users.values().any(|r| r.user.department.contains(&id)) {
+            return api_error(ApiErrorCode::DepartmentHasUsers, "department has users");
+        }
+        self.departments.remove(&id);
+        for group in self.groups.values_mut() {
+            group.department.retain(|d| *d != id);
+        }
+        Ok(())
+    }
+
+    pub fn list_department(&self, id: Option<u64>) -> Result<Vec<Department>> {
+        let id = id.unwrap_or(ROOT_DEPARTMENT_ID);
+        self.department(id)?;
+        Ok(self
+            .subtree(id)
+            .into_iter()
+            .map(|id| self.departments[&id].clone())
+            .collect())
+    }
+
+    pub fn search_department(&self, params: ParamsSerchDepartment) -> Result<Vec<Department>> {
+        let fuzzy = params.fuzzy.unwrap_or_default() == 1;
+        Ok(self
+            .departments
+            .values()
+            .filter(|d| {
+                if fuzzy {
+                    d.name.contains(&params.name)
+                } else {
+                    d.name == params.name
+                }
+            })
+            .cloned()
+            .collect())
+    }
+
+    pub fn create_user(&mut self, params: ParamsCreateUser) -> Result<()> {
+        if !is_email(&params.user_id) {
+            return api_error(ApiErrorCode::InvalidUserId, "invalid userid");
+        }
+        if self.account_kind(&params.user_id) != 0 {
+            return api_error(ApiErrorCode::UserIdExists, "userid existed");
+        }
+        Self::check_user_name(&params.name)?;
+        self.check_user_departments(&params.department)?;
+        let slaves = params.slaves.unwrap_or_default();
+        self.check_slaves(&params.user_id, &slaves)?;
+
+        let user = User {
+            user_id: params.user_id.clone(),
+            name: params.name,
+            department: params.department,
+            position: params.position.unwrap_or_default(),
+            mobile: params.mobile.unwrap_or_default(),
+            gender: params.gender,
+            enable: 1,
+            slaves,
+            cpwd_login: params.cpwd_login,
+        };
+        self.users.insert(
+            params.user_id,
+            UserRecord {
+                user,
+                options: HashMap::new(),
+                new_mail_count: 0,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn update_user(&mut self, params: ParamsUpdateUser) -> Result<()> {
+        self.user_mut(&params.user_id)?;
+        if let Some(name) = &params.name {
+            Self::check_user_name(name)?;
+        }
+        if let Some(department) = &params.department {
+            self.check_user_departments(department)?;
+        }
+        if let Some(slaves) = &params.slaves {
+            self.check_slaves(&params.user_id, slaves)?;
+        }
+
+        let user = &mut self.user_mut(&params.user_id)?.user;
+        if let Some(name) = params.name {
+            user.name = name;
+        }
+        if let Some(department) = params.department {
+            user.department = department;
+        }
+        if let Some(position) = params.position {
+            user.position = position;
+        }
+        if let Some(mobile) = params.mobile {
+            user.mobile = mobile;
+        }
+        if let Some(gender) = params.gender {
+            user.gender = Some(gender);
+        }
+        if let Some(slaves) = params.slaves {
+            user.slaves = slaves;
+        }
+        if let Some(enable) = params.enable {
+            user.enable = enable;
+        }
+        if let Some(cpwd_login) = params.cpwd_login {
+            user.cpwd_login = Some(cpwd_login);
+        }
+        Ok(())
+    }
+
+    pub fn delete_user(&mut self, user_id: &str) -> Result<()> {
+        self.user_mut(user_id)?;
+        self.users.remove(user_id);
+        for group in self.groups.values_mut() {
+            group.userlist.retain(|u| u != user_id);
+            group.allow_userlist.retain(|u| u != user_id);
+        }
+        Ok(())
+    }
+
+    pub fn get_user(&self, user_id: &str) -> Result<User> {
+        match self.users.get(user_id) {
+            Some(record) => Ok(record.user.clone()),
+            None => api_error(ApiErrorCode::UserNotFound, "userid not found"),
+        }
+    }
+
+    pub fn get_department_user(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<User>> {
+        self.department(department_id)?;
+        let departments = if fetch_child.unwrap_or_default() {
+            self.subtree(department_id)
+        } else {
+            vec![department_id]
+        };
+        Ok(self
+            .users
+            .values()
+            .filter(|r| r.user.department.iter().any(|d| departments.contains(d)))
+            .map(|r| r.user.clone())
+            .collect())
+    }
+
+    pub fn list_department_user_simple(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<UserSimple>> {
+        Ok(self
+            .get_department_user(department_id, fetch_child)?
+            .into_iter()
+            .map(|u| UserSimple {
+                user_id: u.user_id,
+                name: u.name,
+                department: u.department,
+            })
+            .collect())
+    }
+
+    pub fn batchcheck_user(&self, userids: &[&str]) -> Result<Vec<UserCheck>> {
+        Ok(userids
+            .iter()
+            .map(|user| UserCheck {
+                user: user.to_string(),
+                kind: self.account_kind(user),
+            })
+            .collect())
+    }
+
+    pub fn create_group(&mut self, params: ParamsCreateGroup) -> Result<()> {
+        if !is_email(&params.groupid) {
+            return api_error(ApiErrorCode::InvalidField, "invalid groupid");
+        }
+        if self.account_kind(&params.groupid) != 0 {
+            return api_error(ApiErrorCode::UserIdExists, "groupid existed");
+        }
+        if params.groupname.trim().is_empty() || params.groupname.len() > MAX_NAME_LEN {
+            return api_error(ApiErrorCode::InvalidField, "invalid groupname");
+        }
+        let userlist = params.userlist.unwrap_or_default();
+        let grouplist = params.grouplist.unwrap_or_default();
+        let department = params.department.unwrap_or_default();
+        if userlist.is_empty() && grouplist.is_empty() && department.is_empty() {
+            return api_error(ApiErrorCode::InvalidField, "group has no member");
+        }
+        self.check_group_members(&params.groupid, &userlist, &grouplist, &department)?;
+        if params.allow_type > 3 {
+            return api_error(ApiErrorCode::InvalidField, "invalid allow_type");
+        }
+
+        self.groups.insert(
+            params.groupid.clone(),
+            Group {
+                groupid: params.groupid,
+                groupname: params.groupname,
+                userlist,
+                grouplist,
+                department,
+                allow_type: params.allow_type,
+                allow_userlist: params.allow_userlist.into_iter().collect(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn update_group(&mut self, params: ParamsUpdateGroup) -> Result<()> {
+        let mut group = self.get_group(&params.groupid)?;
+        if let Some(groupname) = params.groupname {
+            if groupname.trim().is_empty() || groupname.len() > MAX_NAME_LEN {
+                return api_error(ApiErrorCode::InvalidField, "invalid groupname");
+            }
+            group.groupname = groupname;
+        }
+        if let Some(userlist) = params.userlist {
+            group.userlist = userlist;
+        }
+        if let Some(grouplist) = params.grouplist {
+            group.grouplist = grouplist;
+        }
+        if let Some(department) = params.department {
+            group.department = department;
+        }
+        if let Some(allow_type) = params.allow_type {
+            if allow_type > 3 {
+                return api_error(ApiErrorCode::InvalidField, "invalid allow_type");
+            }
+            group.allow_type = allow_type;
+        }
+        if let Some(allow_userlist) = params.allow_userlist {
+            group.allow_userlist = vec![allow_userlist];
+        }
+        self.check_group_members(
+            &group.groupid,
+            &group.userlist,
+            &group.grouplist,
+            &group.department,
+        )?;
+
+        self.groups.insert(group.groupid.clone(), group);
+        Ok(())
+    }
+
+    pub fn delete_group(&mut self, group_id: &str) -> Result<()> {
+        self.get_group(group_id)?;
+        self.groups.remove(group_id);
+        for group in self.groups.values_mut() {
+            group.grouplist.retain(|g| g != group_id);
+        }
+        Ok(())
+    }
+
+    pub fn get_group(&self, group_id: &str) -> Result<Group> {
+        match self.groups.get(group_id) {
+            Some(group) => Ok(group.clone()),
+            None => api_error(ApiErrorCode::UserNotFound, "groupid not found"),
+        }
+    }
+
+    pub fn get_user_option(
+        &self,
+        user_id: &str,
+        types: &[UserOption],
+    ) -> Result<Vec<UserOptionValue>> {
+        let Some(record) = self.users.get(user_id) else {
+            return api_error(ApiErrorCode::UserNotFound, "userid not found");
+        };
+        let types = if types.is_empty() {
+            UserOption::ALL.as_slice()
+        } else {
+            types
+        };
+        Ok(types
+            .iter()
+            .map(|option| {
+                let enabled = record.options.get(option).copied().unwrap_or_default();
+                UserOptionValue::new(*option, enabled)
+            })
+            .collect())
+    }
+
+    pub fn update_user_option(&mut self, user_id: &str, options: &[UserOptionValue]) -> Result<()> {
+        let record = self.user_mut(user_id)?;
+        for option in options {
+            record.options.insert(option.option, option.enabled);
+        }
+        Ok(())
+    }
+
+    pub fn get_login_url(&self, user_id: &str) -> Result<LoginUrl> {
+        self.get_user(user_id)?;
+        Ok(LoginUrl {
+            login_url: format!(
+                "https://exmail.qq.com/cgi-bin/login?fun=bizopenssologin&userid={user_id}"
+            ),
+            expires_in: 300,
+            fetched_at: Instant::now(),
+        })
+    }
+
+    pub fn get_new_mail_count(&self, user_id: &str) -> Result<NewMailCount> {
+        match self.users.get(user_id) {
+            Some(record) => Ok(NewMailCount {
+                count: record.new_mail_count,
+            }),
+            None => api_error(ApiErrorCode::UserNotFound, "userid not found"),
+        }
+    }
+}
+
+/// 内存实现的[`Exmailer`]，clone出的实例共享同一份[`Directory`]
+#[derive(Debug, Clone, Default)]
+pub struct FakeExmail {
+    directory: Arc<Mutex<Directory>>,
+}
+
+impl FakeExmail {
+    /// 只包含根部门的通讯录
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用已有的通讯录
+    pub fn with_directory(directory: Directory) -> Self {
+        FakeExmail {
+            directory: Arc::new(Mutex::new(directory)),
+        }
+    }
+
+    /// 直接读写通讯录，用于准备测试数据或检查结果
+    pub fn directory(&self) -> MutexGuard<'_, Directory> {
+        self.directory.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl Exmailer for FakeExmail {
+    async fn create_department(&self, params: ParamsCreateDepartment) -> Result<u64> {
+        self.directory().create_department(params)
+    }
+
+    async fn update_department(&self, params: ParamsUpdateDepartment) -> Result<()> {
+        self.directory().update_department(params)
+    }
+
+    async fn delete_department(&self, id: u64) -> Result<()> {
+        self.directory().delete_department(id)
+    }
+
+    async fn list_department(&self, id: Option<u64>) -> Result<Vec<Department>> {
+        self.directory().list_department(id)
+    }
+
+    async fn search_department(&self, params: ParamsSerchDepartment) -> Result<Vec<Department>> {
+        self.directory().search_department(params)
+    }
+
+    async fn create_user(&self, params: ParamsCreateUser) -> Result<()> {
+        self.directory().create_user(params)
+    }
+
+    async fn update_user(&self, params: ParamsUpdateUser) -> Result<()> {
+        self.directory().update_user(params)
+    }
+
+    async fn delete_user(&self, user_id: &str) -> Result<()> {
+        self.directory().delete_user(user_id)
+    }
+
+    async fn get_user(&self, user_id: &str) -> Result<User> {
+        self.directory().get_user(user_id)
+    }
+
+    async fn get_department_user(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<User>> {
+        self.directory()
+            .get_department_user(department_id, fetch_child)
+    }
+
+    async fn list_department_user_simple(
+        &self,
+        department_id: u64,
+        fetch_child: Option<bool>,
+    ) -> Result<Vec<UserSimple>> {
+        self.directory()
+            .list_department_user_simple(department_id, fetch_child)
+    }
+
+    async fn batchcheck_user(&self, userids: &[&str]) -> Result<Vec<UserCheck>> {
+        self.directory().batchcheck_user(userids)
+    }
+
+    async fn create_group(&self, params: ParamsCreateGroup) -> Result<()> {
+        self.directory().create_group(params)
+    }
+
+    async fn update_group(&self, params: ParamsUpdateGroup) -> Result<()> {
+        self.directory().update_group(params)
+    }
+
+    async fn delete_group(&self, group_id: &str) -> Result<()> {
+        self.directory().delete_group(group_id)
+    }
+
+    async fn get_group(&self, group_id: &str) -> Result<Group> {
+        self.directory().get_group(group_id)
+    }
+
+    async fn get_user_option(
+        &self,
+        user_id: &str,
+        types: &[UserOption],
+    ) -> Result<Vec<UserOptionValue>> {
+        self.directory().get_user_option(user_id, types)
+    }
+
+    async fn update_user_option(&self, user_id: &str, options: &[UserOptionValue]) -> Result<()> {
+        self.directory().update_user_option(user_id, options)
+    }
+
+    async fn get_login_url(&self, user_id: &str) -> Result<LoginUrl> {
+        self.directory().get_login_url(user_id)
+    }
+
+    async fn get_new_mail_count(
+        &self,
+        user_id: &str,
+        _begin_date: NaiveDate,
+        _end_date: NaiveDate,
+    ) -> Result<NewMailCount> {
+        self.directory().get_new_mail_count(user_id)
+    }
+}
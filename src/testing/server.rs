@@ -0,0 +1,308 @@
+//! 模拟腾讯企业邮箱http接口的本地服务
+//!
+//! 以[`Directory`]作为数据，实现access_token、部门、成员、邮件群组等接口，
+//! 可配置延迟、随机错误及token有效期。任意http客户端将接口地址指向该服务即可，
+//! 也可通过`rtxmail-mock`命令单独运行。
+//!
+//! ```no_run
+//! use rtxmail::{testing::server::{MockConfig, MockExmailServer}, Client};
+//!
+//! # async fn run() -> rtxmail::errs::Result<()> {
+//! let server = MockExmailServer::new(MockConfig::default());
+//! let addr = server.start("127.0.0.1:0").await?;
+//! let client = Client::builder("corp_id", "corp_secret")
+//!     .base_url(format!("http://{addr}"))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{Directory, FakeExmail};
+use crate::errs::{new_api_error, ApiErrorCode, Error, Result};
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::Uri,
+    Json, Router,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+/// 模拟服务配置
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// 获取token时校验的corpid
+    pub corp_id: String,
+    /// 获取token时校验的corpsecret
+    pub corp_secret: String,
+    /// access_token有效期
+    pub token_ttl: Duration,
+    /// 每个请求的额外延迟
+    pub latency: Duration,
+    /// 随机返回错误的概率，取值0.0~1.0，不影响获取token
+    pub error_rate: f64,
+    /// 随机错误使用的错误码
+    pub error_code: ApiErrorCode,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        MockConfig {
+            corp_id: "corp_id".to_owned(),
+            corp_secret: "corp_secret".to_owned(),
+            token_ttl: Duration::from_secs(7200),
+            latency: Duration::ZERO,
+            error_rate: 0.0,
+            error_code: ApiErrorCode::SystemBusy,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    /// 已签发的token及其过期时间
+    tokens: HashMap<String, Instant>,
+    /// 依次返回的预设错误
+    errors: VecDeque<ApiErrorCode>,
+}
+
+/// 模拟腾讯企业邮箱接口的http服务，clone出的实例共享数据
+#[derive(Debug, Clone)]
+pub struct MockExmailServer {
+    config: MockConfig,
+    fake: FakeExmail,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockExmailServer {
+    /// 只包含根部门的模拟服务
+    pub fn new(config: MockConfig) -> Self {
+        Self::with_fake(config, FakeExmail::new())
+    }
+
+    /// 与`fake`共享通讯录，可通过`fake`准备数据或检查结果
+    pub fn with_fake(config: MockConfig, fake: FakeExmail) -> Self {
+        MockExmailServer {
+            config,
+            fake,
+            state: Arc::default(),
+        }
+    }
+
+    /// 服务使用的通讯录
+    pub fn fake(&self) -> &FakeExmail {
+        &self.fake
+    }
+
+    /// 之后的`count`个接口请求依次返回`code`错误，不影响获取token
+    pub fn inject_errors(&self, code: ApiErrorCode, count: usize) {
+        let mut state = self.state();
+        state.errors.extend(std::iter::repeat_n(code, count));
+    }
+
+    /// 使已签发的token全部过期
+    pub fn expire_tokens(&self) {
+        let now = Instant::now();
+        for expires_at in self.state().tokens.values_mut() {
+            *expires_at = now;
+        }
+    }
+
+    /// 可挂载到其他服务中的路由
+    pub fn router(&self) -> Router {
+        Router::new().fallback(handler).with_state(self.clone())
+    }
+
+    /// 监听`addr`并在后台运行，返回实际监听的地址
+    pub async fn start(&self, addr: impl ToSocketAddrs) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let app = self.router();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        Ok(local_addr)
+    }
+
+    /// 在`listener`上运行直到出错
+    pub async fn serve(&self, listener: TcpListener) -> Result<()> {
+        axum::serve(listener, self.router()).await?;
+        Ok(())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn get_token(&self, query: &HashMap<String, String>) -> Result<Value> {
+        if query.get("corpid") != Some(&self.config.corp_id) {
+            return api_error(ApiErrorCode::InvalidCorpId, "invalid corpid");
+        }
+        if query.get("corpsecret") != Some(&self.config.corp_secret) {
+            return api_error(ApiErrorCode::InvalidSecret, "invalid credential");
+        }
+        let token: String = std::iter::repeat_with(fastrand::alphanumeric)
+            .take(32)
+            .collect();
+        self.state()
+            .tokens
+            .insert(token.clone(), Instant::now() + self.config.token_ttl);
+        Ok(json!({
+            "access_token": token,
+            "expires_in": self.config.token_ttl.as_secs(),
+        }))
+    }
+
+    fn check_token(&self, query: &HashMap<String, String>) -> Result<()> {
+        let mut state = self.state();
+        match query.get("access_token").and_then(|t| state.tokens.get(t)) {
+            None => api_error(ApiErrorCode::InvalidAccessToken, "invalid access_token"),
+            Some(expires_at) if *expires_at <= Instant::now() => {
+                api_error(ApiErrorCode::AccessTokenExpired, "access_token expired")
+            }
+            Some(_) => match state.errors.pop_front() {
+                Some(code) => api_error(code, "injected error"),
+                None if fastrand::f64() < self.config.error_rate => {
+                    api_error(self.config.error_code, "injected error")
+                }
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn dispatch(&self, path: &str, query: &HashMap<String, String>, body: &[u8]) -> Result<Value> {
+        if path == "/cgi-bin/gettoken" {
+            return self.get_token(query);
+        }
+        self.check_token(query)?;
+
+        let mut dir = self.fake.directory();
+        let resp = match path {
+            "/cgi-bin/department/create" => json!({"id": dir.create_department(parse(body)?)?}),
+            "/cgi-bin/department/update" => empty(dir.update_department(parse(body)?)?),
+            "/cgi-bin/department/delete" => empty(dir.delete_department(param(query, "id")?)?),
+            "/cgi-bin/department/list" => {
+                let id = query.get("id").and_then(|id| id.parse().ok());
+                json!({"department": dir.list_department(id)?})
+            }
+            "/cgi-bin/department/search" => {
+                json!({"department": dir.search_department(parse(body)?)?})
+            }
+            "/cgi-bin/user/create" => empty(dir.create_user(parse(body)?)?),
+            "/cgi-bin/user/update" => empty(dir.update_user(parse(body)?)?),
+            "/cgi-bin/user/delete" => empty(dir.delete_user(&param::<String>(query, "userid")?)?),
+            "/cgi-bin/user/get" => {
+                serde_json::to_value(dir.get_user(&param::<String>(query, "userid")?)?)?
+            }
+            "/cgi-bin/user/list" | "/cgi-bin/user/simplelist" => {
+                let department_id = param(query, "department_id")?;
+                let fetch_child = query.get("fetch_child").map(|v| v == "1");
+                if path == "/cgi-bin/user/list" {
+                    json!({"userlist": dir.get_department_user(department_id, fetch_child)?})
+                } else {
+                    json!({"userlist": dir.list_department_user_simple(department_id, fetch_child)?})
+                }
+            }
+            "/cgi-bin/user/batchcheck" => {
+                let body: Value = parse(body)?;
+                let users: Vec<&str> = body["userlist"]
+                    .as_array()
+                    .map(|users| users.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                json!({"list": dir.batchcheck_user(&users)?})
+            }
+            "/cgi-bin/group/create" => empty(dir.create_group(parse(body)?)?),
+            "/cgi-bin/group/update" => empty(dir.update_group(parse(body)?)?),
+            "/cgi-bin/group/delete" => {
+                empty(dir.delete_group(&param::<String>(query, "groupid")?)?)
+            }
+            "/cgi-bin/group/get" => {
+                serde_json::to_value(dir.get_group(&param::<String>(query, "groupid")?)?)?
+            }
+            "/cgi-bin/useroption/get" => {
+                let body: Value = parse(body)?;
+                let types: Vec<_> =
+                    serde_json::from_value(body["type"].clone()).unwrap_or_default();
+                let user_id = body["userid"].as_str().unwrap_or_default();
+                json!({"option": dir.get_user_option(user_id, &types)?})
+            }
+            "/cgi-bin/useroption/update" => {
+                let body: Value = parse(body)?;
+                let options: Vec<_> =
+                    serde_json::from_value(body["option"].clone()).unwrap_or_default();
+                let user_id = body["userid"].as_str().unwrap_or_default();
+                empty(dir.update_user_option(user_id, &options)?)
+            }
+            "/cgi-bin/service/get_login_url" => {
+                serde_json::to_value(dir.get_login_url(&param::<String>(query, "userid")?)?)?
+            }
+            "/cgi-bin/mail/newcount" => {
+                serde_json::to_value(dir.get_new_mail_count(&param::<String>(query, "userid")?)?)?
+            }
+            _ => return api_error(ApiErrorCode::Unknown(404), "api not found"),
+        };
+        Ok(resp)
+    }
+}
+
+impl From<FakeExmail> for MockExmailServer {
+    fn from(fake: FakeExmail) -> Self {
+        Self::with_fake(MockConfig::default(), fake)
+    }
+}
+
+impl From<Directory> for MockExmailServer {
+    fn from(directory: Directory) -> Self {
+        FakeExmail::with_directory(directory).into()
+    }
+}
+
+fn api_error<T>(code: ApiErrorCode, message: &str) -> Result<T> {
+    Err(new_api_error(code.code(), message.to_owned()))
+}
+
+fn empty(_: ()) -> Value {
+    json!({})
+}
+
+/// 解析json body，格式错误时返回字段不合法
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body)
+        .or_else(|err| api_error(ApiErrorCode::InvalidField, &err.to_string()))
+}
+
+/// 读取查询参数，缺少或格式错误时返回字段不合法
+fn param<T: std::str::FromStr>(query: &HashMap<String, String>, name: &str) -> Result<T> {
+    match query.get(name).and_then(|v| v.parse().ok()) {
+        Some(value) => Ok(value),
+        None => api_error(ApiErrorCode::InvalidField, &format!("invalid {name}")),
+    }
+}
+
+async fn handler(
+    State(server): State<MockExmailServer>,
+    uri: Uri,
+    Query(query): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> Json<Value> {
+    if !server.config.latency.is_zero() {
+        tokio::time::sleep(server.config.latency).await;
+    }
+    let resp = match server.dispatch(uri.path(), &query, &body) {
+        Ok(mut resp) => {
+            resp["errcode"] = json!(0);
+            resp["errmsg"] = json!("ok");
+            resp
+        }
+        Err(Error::ApiError { code, message }) => {
+            json!({"errcode": code.code(), "errmsg": message})
+        }
+        Err(err) => json!({"errcode": ApiErrorCode::SystemBusy.code(), "errmsg": err.to_string()}),
+    };
+    Json(resp)
+}
@@ -0,0 +1,346 @@
+//! access_token缓存
+//!
+//! [`Client`](crate::Client)通过[`TokenStore`]读写access_token，默认使用进程内的[`MemoryTokenStore`]。
+//! 同一主机上的多个进程可共用同一个[`FileTokenStore`]文件，避免各自调用`/cgi-bin/gettoken`消耗token配额。
+
+use crate::errs::Result;
+use async_trait::async_trait;
+use fs2::FileExt;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Debug,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
+
+/// access_token默认提前刷新时间
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// 缓存的access_token
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Token {
+    pub access_token: String,
+    /// 获取时间，unix时间戳(秒)
+    pub create_at: u64,
+    /// 有效期(秒)
+    pub expires_in: u64,
+}
+
+impl Token {
+    pub fn new(access_token: String, expires_in: u64) -> Self {
+        Token {
+            access_token,
+            create_at: unix_now(),
+            expires_in,
+        }
+    }
+
+    /// 过期时间，unix时间戳(秒)
+    pub fn expires_at(&self) -> u64 {
+        self.create_at.saturating_add(self.expires_in)
+    }
+
+    /// 是否需要刷新：距离过期不足`margin`时即提前刷新。
+    ///
+    /// `margin`最多取有效期的一半，避免有效期很短时每次请求都重新获取
+    pub fn needs_refresh(&self, margin: Duration) -> bool {
+        let margin = margin.as_secs().min(self.expires_in / 2);
+        unix_now().saturating_add(margin) >= self.expires_at()
+    }
+}
+
+/// access_token存储
+#[async_trait]
+pub trait TokenStore: Debug + Send + Sync {
+    /// 读取token，不存在时返回`None`。是否过期由调用方根据[`Token::needs_refresh`]判断
+    async fn get(&self) -> Result<Option<Token>>;
+    /// 保存token
+    async fn set(&self, token: Token) -> Result<()>;
+    /// 使token失效，仅当存储的仍是`access_token`时才清除，避免误删其他进程刚刷新的token
+    async fn invalidate(&self, access_token: &str) -> Result<()>;
+    /// 读取token，不存在或距离过期不足`margin`时调用`fetch`获取新token并保存。
+    ///
+    /// 默认实现依次调用[`get`](Self::get)和[`set`](Self::set)，不保证原子性。
+    /// 多个进程共享的存储应在整个过程中持有锁，保证只有一个进程调用`fetch`
+    async fn get_or_refresh<'a>(
+        &'a self,
+        margin: Duration,
+        fetch: BoxFuture<'a, Result<Token>>,
+    ) -> Result<Token> {
+        if let Some(token) = self.get().await?.filter(|x| !x.needs_refresh(margin)) {
+            return Ok(token);
+        }
+        let token = fetch.await?;
+        self.set(token.clone()).await?;
+        Ok(token)
+    }
+}
+
+/// 进程内token存储
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    token: Mutex<Option<Token>>,
+}
+
+impl MemoryTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TokenStore for MemoryTokenStore {
+    async fn get(&self) -> Result<Option<Token>> {
+        Ok(self.token.lock().await.clone())
+    }
+
+    async fn set(&self, token: Token) -> Result<()> {
+        *self.token.lock().await = Some(token);
+        Ok(())
+    }
+
+    async fn invalidate(&self, access_token: &str) -> Result<()> {
+        let mut token = self.token.lock().await;
+        if matches!(&*token, Some(x) if x.access_token == access_token) {
+            *token = None;
+        }
+        Ok(())
+    }
+}
+
+/// 基于文件的token存储，多个进程可共享同一文件。
+///
+/// 读写时对`<path>.lock`加文件锁，写入先写临时文件再重命名，保证其他进程不会读到写了一半的内容。
+/// 刷新token时在获取、写入期间一直持有文件锁，多个进程同时发现token过期时只有一个进程调用接口。
+/// 读取过的token缓存在内存中，只有缓存为空(未读取或已失效)时才读取文件。
+/// 不同企业(corp_id)请使用不同的文件
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    /// 最近读写的token
+    cache: Arc<std::sync::Mutex<Option<Token>>>,
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileTokenStore {
+            path: path.into(),
+            cache: Arc::default(),
+        }
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, Option<Token>> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 获取文件锁，返回的文件关闭时释放
+    async fn lock(&self) -> Result<File> {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || lock_file(&path))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// 在后台线程中执行文件读写
+    async fn blocking<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Path) -> Result<T> + Send + 'static,
+    {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || f(&path))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// 在文件锁保护下执行`f`
+    async fn with_lock<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Path) -> Result<T> + Send + 'static,
+    {
+        self.blocking(move |path| {
+            let lock = lock_file(path)?;
+            let ret = f(path);
+            lock.unlock()?;
+            ret
+        })
+        .await
+    }
+}
+
+/// 对`<path>.lock`加排他锁
+fn lock_file(path: &Path) -> Result<File> {
+    let mut lock_path = path.to_owned().into_os_string();
+    lock_path.push(".lock");
+    let lock = private_options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    lock.lock_exclusive()?;
+    Ok(lock)
+}
+
+/// 新建文件只允许当前用户读写，避免其他用户读取token
+fn private_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+}
+
+fn read_token(path: &Path) -> Result<Option<Token>> {
+    match fs::read(path) {
+        Ok(data) if data.is_empty() => Ok(None),
+        Ok(data) => Ok(serde_json::from_slice(&data)?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_token(path: &Path, token: Option<&Token>) -> Result<()> {
+    let mut tmp_path = path.to_owned().into_os_string();
+    tmp_path.push(".tmp");
+    let mut file = private_options()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(&tmp_path)?;
+    if let Some(token) = token {
+        file.write_all(&serde_json::to_vec(token)?)?;
+    }
+    file.sync_all()?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[async_trait]
+impl TokenStore for FileTokenStore {
+    async fn get(&self) -> Result<Option<Token>> {
+        if let Some(token) = self.cache().clone() {
+            return Ok(Some(token));
+        }
+        let token = self.with_lock(read_token).await?;
+        self.cache().clone_from(&token);
+        Ok(token)
+    }
+
+    async fn set(&self, token: Token) -> Result<()> {
+        *self.cache() = Some(token.clone());
+        self.with_lock(move |path| write_token(path, Some(&token)))
+            .await
+    }
+
+    async fn invalidate(&self, access_token: &str) -> Result<()> {
+        {
+            let mut cache = self.cache();
+            if matches!(&*cache, Some(x) if x.access_token == access_token) {
+                *cache = None;
+            }
+        }
+        let access_token = access_token.to_owned();
+        self.with_lock(move |path| match read_token(path)? {
+            Some(x) if x.access_token == access_token => write_token(path, None),
+            _ => Ok(()),
+        })
+        .await
+    }
+
+    async fn get_or_refresh<'a>(
+        &'a self,
+        margin: Duration,
+        fetch: BoxFuture<'a, Result<Token>>,
+    ) -> Result<Token> {
+        let lock = self.lock().await?;
+        let current = self.blocking(read_token).await?;
+        let token = match current.filter(|x| !x.needs_refresh(margin)) {
+            Some(token) => token,
+            None => {
+                let token = fetch.await?;
+                let new_token = token.clone();
+                self.blocking(move |path| write_token(path, Some(&new_token)))
+                    .await?;
+                token
+            }
+        };
+        lock.unlock()?;
+        *self.cache() = Some(token.clone());
+        Ok(token)
+    }
+}
+
+/// 当前unix时间戳(秒)
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_refresh_honours_margin() {
+        let token = Token::new("t".to_owned(), 7200);
+        assert!(!token.needs_refresh(DEFAULT_REFRESH_MARGIN));
+        assert!(!token.needs_refresh(Duration::from_secs(7200)));
+
+        let expiring = Token {
+            create_at: unix_now() - 7000,
+            ..token.clone()
+        };
+        assert!(expiring.needs_refresh(DEFAULT_REFRESH_MARGIN));
+        assert!(!expiring.needs_refresh(Duration::ZERO));
+
+        let expired = Token {
+            create_at: unix_now() - 7200,
+            ..token
+        };
+        assert!(expired.needs_refresh(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn file_store_is_shared_between_instances() {
+        let path = std::env::temp_dir().join(format!("rtxmail-token-{}.json", std::process::id()));
+        let a = FileTokenStore::new(&path);
+        let b = FileTokenStore::new(&path);
+
+        assert!(a.get().await.unwrap().is_none());
+        a.set(Token::new("t1".to_owned(), 7200)).await.unwrap();
+        assert_eq!(b.get().await.unwrap().unwrap().access_token, "t1");
+
+        // 只清除仍是该token的缓存
+        b.invalidate("t0").await.unwrap();
+        assert!(FileTokenStore::new(&path).get().await.unwrap().is_some());
+        b.invalidate("t1").await.unwrap();
+        assert!(b.get().await.unwrap().is_none());
+        assert!(FileTokenStore::new(&path).get().await.unwrap().is_none());
+
+        // 读取过的token缓存在内存中，失效后才重新读取文件
+        assert_eq!(a.get().await.unwrap().unwrap().access_token, "t1");
+        b.set(Token::new("t2".to_owned(), 7200)).await.unwrap();
+        a.invalidate("t1").await.unwrap();
+        assert_eq!(a.get().await.unwrap().unwrap().access_token, "t2");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut lock_path = path.clone().into_os_string();
+            lock_path.push(".lock");
+            for p in [path.as_os_str(), lock_path.as_os_str()] {
+                let mode = fs::metadata(p).unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+            let _ = fs::remove_file(lock_path);
+        }
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -1,20 +1,55 @@
-use crate::errs::{new_http_error, Result};
+use crate::{
+    cassette::{Cassette, CassetteMode},
+    errs::{new_http_error, Result},
+};
 use reqwest::{
-    header::{HeaderName, HeaderValue},
-    Client, Method, Response,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client, Method,
 };
 use serde_json::Value;
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 use tracing::debug;
 
-/// 获取http client，如果设置了PROXY环境变量，则将使用PROXY代理
-fn get_http_client() -> Result<Client> {
-    let mut client_builder = reqwest::Client::builder();
-    if let Ok(proxy) = std::env::var("PROXY") {
-        debug!("PROXY is {}", &proxy);
-        client_builder = client_builder.proxy(reqwest::Proxy::https(&proxy)?);
+/// http client配置
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HttpOptions {
+    /// 连接超时时间
+    pub connect_timeout: Option<Duration>,
+    /// 请求超时时间(包含读取响应)
+    pub timeout: Option<Duration>,
+    /// 代理地址，未设置时读取PROXY环境变量
+    pub proxy: Option<String>,
+    /// User-Agent
+    pub user_agent: Option<String>,
+    /// 每个请求默认携带的header
+    pub headers: HeaderMap,
+}
+
+/// 获取http client，未显式配置代理时如果设置了PROXY环境变量，则将使用PROXY代理
+pub(crate) fn get_http_client(opts: &HttpOptions) -> reqwest::Result<Client> {
+    let mut client_builder = reqwest::Client::builder().default_headers(opts.headers.clone());
+    match &opts.proxy {
+        Some(proxy) => {
+            debug!("proxy is {}", proxy);
+            client_builder = client_builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        None => {
+            if let Ok(proxy) = std::env::var("PROXY") {
+                debug!("PROXY is {}", &proxy);
+                client_builder = client_builder.proxy(reqwest::Proxy::https(&proxy)?);
+            }
+        }
+    }
+    if let Some(timeout) = opts.connect_timeout {
+        client_builder = client_builder.connect_timeout(timeout);
     }
-    Ok(client_builder.build()?)
+    if let Some(timeout) = opts.timeout {
+        client_builder = client_builder.timeout(timeout);
+    }
+    if let Some(user_agent) = &opts.user_agent {
+        client_builder = client_builder.user_agent(user_agent);
+    }
+    client_builder.build()
 }
 
 #[derive(Debug, Default)]
@@ -39,14 +74,17 @@ impl PostParameters {
     }
 }
 
-#[tracing::instrument]
+/// 发送请求并返回响应内容，设置了`cassette`时按其模式录制或回放
+#[tracing::instrument(skip(client, cassette, headers, query, body))]
 pub async fn do_http(
+    client: &Client,
+    cassette: Option<&Cassette>,
     method: Method,
     req_url: &str,
     headers: Option<HashMap<HeaderName, String>>,
     query: Option<Value>,
     body: Option<PostParameters>,
-) -> Result<Response> {
+) -> Result<String> {
     debug!("request url: {}", req_url);
 
     // debug!(
@@ -54,8 +92,6 @@ pub async fn do_http(
     //     req_url, method, headers, query, body
     // );
 
-    let client = get_http_client()?;
-
     let mut req_builder = client.get(req_url);
     // .header(reqwest::header::CONTENT_TYPE, "application/json");
 
@@ -65,6 +101,7 @@ pub async fn do_http(
     }
 
     // post params setting
+    let json_body = body.as_ref().and_then(|params| params.json.clone());
     if let Some(params) = body {
         if let Some(body) = params.json {
             req_builder = req_builder.json(&body);
@@ -87,13 +124,21 @@ pub async fn do_http(
     let m = req.method_mut();
     *m = method;
 
+    if let Some(cassette) = cassette.filter(|c| c.mode() == CassetteMode::Replay) {
+        return cassette.replay_response(req.method(), req.url(), json_body.as_ref());
+    }
+    let (method, url) = (req.method().clone(), req.url().clone());
+
     let resp = client.execute(req).await?;
 
     let status_code = resp.status();
+    let text = resp.text().await?;
+    if let Some(cassette) = cassette {
+        cassette.record_response(&method, &url, json_body.as_ref(), status_code, &text);
+    }
     if !status_code.is_success() {
-        let msg = resp.text().await?;
-        return Err(new_http_error(req_url.to_owned(), status_code, msg));
+        return Err(new_http_error(req_url.to_owned(), status_code, text));
     }
 
-    Ok(resp)
+    Ok(text)
 }
@@ -9,3 +9,125 @@
 //         }
 //     }
 // }
+
+/// 定义以整数表示的枚举，未收录的取值保存在`Unknown`中。
+///
+/// 反序列化时同时兼容数字及数字字符串，如`1`与`"1"`
+macro_rules! int_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident: $repr:ty {
+            $($(#[$vmeta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($(#[$vmeta])* $variant,)*
+            /// 未收录的取值
+            Unknown($repr),
+        }
+
+        impl From<$repr> for $name {
+            fn from(value: $repr) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    value => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for $repr {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl ::serde::Serialize for $name {
+            fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                ::serde::Serialize::serialize(&<$repr>::from(*self), serializer)
+            }
+        }
+
+        impl<'de> ::serde::Deserialize<'de> for $name {
+            fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                #[derive(::serde::Deserialize)]
+                #[serde(untagged)]
+                enum Repr {
+                    Int($repr),
+                    Str(String),
+                }
+                match <Repr as ::serde::Deserialize>::deserialize(deserializer)? {
+                    Repr::Int(value) => Ok(value.into()),
+                    Repr::Str(value) => value
+                        .trim()
+                        .parse::<$repr>()
+                        .map(Into::into)
+                        .map_err(::serde::de::Error::custom),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use int_enum;
+
+/// 以字符串`"0"`/`"1"`表示的布尔值，反序列化时同时兼容数字及布尔值
+pub(crate) mod bool_string {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(if *value { "1" } else { "0" })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Bool(bool),
+            Int(i64),
+            Str(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Bool(value) => Ok(value),
+            Repr::Int(value) => Ok(value != 0),
+            Repr::Str(value) => match value.trim() {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                value => Err(D::Error::custom(format!("invalid bool string: {value}"))),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    int_enum! {
+        enum Color: i8 {
+            Red = 1,
+            Blue = 2,
+        }
+    }
+
+    #[test]
+    fn int_enum_accepts_numbers_and_strings() {
+        let colors: Vec<Color> = serde_json::from_str(r#"[1, "2", -3]"#).unwrap();
+        assert_eq!(colors, [Color::Red, Color::Blue, Color::Unknown(-3)]);
+        assert_eq!(serde_json::to_string(&colors).unwrap(), "[1,2,-3]");
+    }
+
+    #[derive(Debug, serde::Deserialize, serde::Serialize)]
+    struct Flag(#[serde(with = "super::bool_string")] bool);
+
+    #[test]
+    fn bool_string_round_trip() {
+        let flags: Vec<Flag> = serde_json::from_str(r#"["1", "0", 1, false]"#).unwrap();
+        let flags: Vec<bool> = flags.into_iter().map(|f| f.0).collect();
+        assert_eq!(flags, [true, false, true, false]);
+        assert_eq!(serde_json::to_string(&Flag(true)).unwrap(), r#""1""#);
+        assert!(serde_json::from_str::<Flag>(r#""yes""#).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::MockServer;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rtxmail::{client::Exmailer, errs::Error, ratelimit::RateLimit, retry::RetryPolicy, Client};
+use std::time::{Duration, Instant};
+
+fn builder(server: &MockServer) -> rtxmail::client::ClientBuilder {
+    Client::builder("corp_id", "corp_secret")
+        .base_url(&server.base_url)
+        .retry_policy(RetryPolicy::none())
+}
+
+#[tokio::test]
+async fn custom_headers_and_user_agent_are_sent() {
+    let server = MockServer::start().await;
+    let client = builder(&server)
+        .user_agent("my-sync/1.0")
+        .header(
+            HeaderName::from_static("x-request-source"),
+            HeaderValue::from_static("sync"),
+        )
+        .build()
+        .unwrap();
+    client.delete_user("a@gzdev.com").await.unwrap();
+
+    // 获取token的请求同样携带
+    for request in server.all_requests() {
+        assert_eq!(request.headers["user-agent"], "my-sync/1.0");
+        assert_eq!(request.headers["x-request-source"], "sync");
+    }
+}
+
+#[tokio::test]
+async fn request_times_out() {
+    let server = MockServer::start().await;
+    server.delay("/cgi-bin/user/delete", Duration::from_secs(2));
+    let client = builder(&server)
+        .timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+    match client.delete_user("a@gzdev.com").await.unwrap_err() {
+        Error::Reqwest(err) => assert!(err.is_timeout()),
+        err => panic!("unexpected error: {err:?}"),
+    }
+}
+
+#[tokio::test]
+async fn injected_http_client_is_used() {
+    let server = MockServer::start().await;
+    let mut headers = HeaderMap::new();
+    headers.insert("x-shared-client", HeaderValue::from_static("1"));
+    let http_client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    // 注入的http client优先，builder上的http配置不生效
+    let client = builder(&server)
+        .user_agent("ignored/1.0")
+        .http_client(http_client)
+        .build()
+        .unwrap();
+    client.delete_user("a@gzdev.com").await.unwrap();
+
+    let request = server.last_request();
+    assert_eq!(request.headers["x-shared-client"], "1");
+    assert!(request.headers.get("user-agent").is_none());
+}
+
+#[tokio::test]
+async fn requests_go_through_proxy() {
+    let server = MockServer::start().await;
+    let client = Client::builder("corp_id", "corp_secret")
+        .base_url("http://exmail.invalid")
+        .retry_policy(RetryPolicy::none())
+        .proxy(&server.base_url)
+        .build()
+        .unwrap();
+    client.delete_user("a@gzdev.com").await.unwrap();
+
+    let request = server.last_request();
+    assert_eq!(request.path, "/cgi-bin/user/delete");
+    assert_eq!(request.headers["host"], "exmail.invalid");
+}
+
+#[tokio::test]
+async fn endpoint_rate_limit_only_applies_to_its_path() {
+    let server = MockServer::start().await;
+    let client = builder(&server)
+        .endpoint_rate_limit(
+            "/cgi-bin/user/delete",
+            RateLimit::every(Duration::from_millis(300)),
+        )
+        .build()
+        .unwrap();
+    client.delete_group("g@gzdev.com").await.unwrap();
+
+    let start = Instant::now();
+    client.delete_group("g@gzdev.com").await.unwrap();
+    assert!(start.elapsed() < Duration::from_millis(300));
+
+    client.delete_user("a@gzdev.com").await.unwrap();
+    let start = Instant::now();
+    client.delete_user("b@gzdev.com").await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
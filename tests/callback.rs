@@ -0,0 +1,74 @@
+use rtxmail::callback::{callback_router, CallbackCrypto, CallbackEvent};
+use std::sync::{Arc, Mutex};
+
+fn crypto() -> CallbackCrypto {
+    CallbackCrypto::new(
+        "QDG6eK",
+        "jWmYm7qr5nMoAUwZRjGtBxmz3KA1tkAj3ykkR6q2B2C",
+        "corp_id",
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn callback_router_handles_events() {
+    let events = Arc::new(Mutex::new(vec![]));
+    let received = events.clone();
+    let app = callback_router(crypto(), move |event| {
+        let received = received.clone();
+        async move { received.lock().unwrap().push(event) }
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    let http = reqwest::Client::new();
+    let crypto = crypto();
+
+    // 验证回调地址
+    let echostr = crypto.encrypt("echo");
+    let query = [
+        ("msg_signature", crypto.signature("1", "2", &echostr)),
+        ("timestamp", "1".to_owned()),
+        ("nonce", "2".to_owned()),
+        ("echostr", echostr),
+    ];
+    let resp = http.get(&url).query(&query).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "echo");
+
+    // 推送事件
+    let encrypt = crypto.encrypt(
+        "<xml><Event>change_contact</Event><ChangeType>update_user</ChangeType><UserID>zhangsan@gzdev.com</UserID></xml>",
+    );
+    let query = [
+        ("msg_signature", crypto.signature("1", "2", &encrypt)),
+        ("timestamp", "1".to_owned()),
+        ("nonce", "2".to_owned()),
+    ];
+    let body = format!("<xml><Encrypt><![CDATA[{encrypt}]]></Encrypt></xml>");
+    let resp = http
+        .post(&url)
+        .query(&query)
+        .body(body.clone())
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+    assert_eq!(
+        *events.lock().unwrap(),
+        [CallbackEvent::UpdateUser {
+            user_id: "zhangsan@gzdev.com".to_owned()
+        }]
+    );
+
+    // 签名错误
+    let query = [("msg_signature", "bad"), ("timestamp", "1"), ("nonce", "2")];
+    let resp = http
+        .post(&url)
+        .query(&query)
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), reqwest::StatusCode::FORBIDDEN);
+    assert_eq!(events.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::MockServer;
+use rtxmail::{
+    cassette::{Cassette, CassetteMode, REDACTED},
+    client::*,
+    dto::ParamsCreateUser,
+    retry::RetryPolicy,
+    Client,
+};
+use serde_json::json;
+use std::{path::PathBuf, sync::Arc};
+
+fn cassette_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rtxmail-{}-{name}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn client(base_url: &str, cassette: Arc<Cassette>) -> Client {
+    Client::builder("corp_id", "corp_secret")
+        .base_url(base_url)
+        .retry_policy(RetryPolicy::none())
+        .cassette(cassette)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn record_then_replay() {
+    let path = cassette_path("record_then_replay");
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/department/list",
+        json!({"errcode": 0, "errmsg": "ok", "department": [
+            {"id": 1, "name": "企业", "parentid": 0, "order": 0},
+        ]}),
+    );
+    server.respond(
+        "/cgi-bin/user/get",
+        json!({"errcode": 60111, "errmsg": "userid not found"}),
+    );
+
+    let cassette = Arc::new(Cassette::auto(&path).unwrap());
+    assert_eq!(cassette.mode(), CassetteMode::Record);
+    let recorder = client(&server.base_url, cassette.clone());
+    recorder.list_department(None).await.unwrap();
+    assert!(recorder
+        .get_user("nobody@gzdev.com")
+        .await
+        .unwrap_err()
+        .is_not_found());
+
+    // 录制内容已脱敏
+    let interactions = cassette.interactions();
+    assert_eq!(interactions.len(), 3);
+    assert_eq!(interactions[0].query["corpsecret"], REDACTED);
+    assert!(interactions[0].response.contains(REDACTED));
+    assert!(interactions[1..]
+        .iter()
+        .all(|i| i.query["access_token"] == REDACTED));
+    cassette.save().unwrap();
+    let file = std::fs::read_to_string(&path).unwrap();
+    assert!(!file.contains("corp_secret"));
+
+    // 回放时不访问网络
+    let cassette = Arc::new(Cassette::auto(&path).unwrap());
+    assert_eq!(cassette.mode(), CassetteMode::Replay);
+    let replayer = client("http://127.0.0.1:9", cassette);
+    let departments = replayer.list_department(None).await.unwrap();
+    assert_eq!(departments[0].name, "企业");
+    assert!(replayer
+        .get_user("nobody@gzdev.com")
+        .await
+        .unwrap_err()
+        .is_not_found());
+
+    // 没有匹配的记录
+    let err = replayer.get_user("lisi@gzdev.com").await.unwrap_err();
+    assert!(matches!(err, rtxmail::errs::Error::CassetteMiss { .. }));
+    assert_eq!(server.all_requests().len(), 3);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn redact_password_in_body() {
+    let path = cassette_path("redact_password_in_body");
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/create",
+        json!({"errcode": 0, "errmsg": "created"}),
+    );
+
+    let cassette = Arc::new(Cassette::record(&path));
+    client(&server.base_url, cassette.clone())
+        .create_user(new_user("Passw0rd"))
+        .await
+        .unwrap();
+    cassette.save().unwrap();
+    let file = std::fs::read_to_string(&path).unwrap();
+    assert!(!file.contains("Passw0rd"));
+    assert_eq!(
+        cassette.interactions()[1].body.as_ref().unwrap()["password"],
+        REDACTED
+    );
+
+    // 回放时使用脱敏后的请求体匹配
+    let replayer = client(
+        "http://127.0.0.1:9",
+        Arc::new(Cassette::replay(&path).unwrap()),
+    );
+    replayer.create_user(new_user("Passw0rd")).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn new_user(password: &str) -> ParamsCreateUser {
+    ParamsCreateUser {
+        user_id: "zhangsan@gzdev.com".to_owned(),
+        name: "张三".to_owned(),
+        department: vec![1],
+        position: None,
+        mobile: None,
+        tel: None,
+        ext_id: None,
+        gender: None,
+        slaves: None,
+        password: password.to_owned(),
+        cpwd_login: None,
+    }
+}
+
+#[tokio::test]
+async fn save_on_drop() {
+    let path = cassette_path("save_on_drop");
+    let server = MockServer::start().await;
+    let recorder = client(&server.base_url, Arc::new(Cassette::record(&path)));
+    recorder.delete_user("zhangsan@gzdev.com").await.unwrap();
+    // 录制期间不写文件，drop时写入
+    assert!(!path.exists());
+    drop(recorder);
+
+    let cassette = Cassette::replay(&path).unwrap();
+    assert_eq!(cassette.interactions().len(), 2);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,21 @@
+//! 修改了PROXY环境变量，单独作为一个测试程序运行
+
+use rtxmail::{client::Exmailer, errs::Error, Client};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn new_defers_http_client_error_to_requests() {
+    std::env::set_var("PROXY", "http://[::1");
+    assert!(Client::builder("corp_id", "corp_secret").build().is_err());
+
+    let client = Client::new("corp_id".to_owned(), "corp_secret".to_owned(), None);
+    // 每次请求返回创建时保存的同一个错误，不会重新构建http client
+    let mut errors = vec![];
+    for _ in 0..2 {
+        match client.list_department(None).await.unwrap_err() {
+            Error::HttpClientBuild(err) => errors.push(err),
+            err => panic!("unexpected error: {err:?}"),
+        }
+    }
+    assert!(Arc::ptr_eq(&errors[0], &errors[1]));
+}
@@ -0,0 +1,146 @@
+//! 集成测试用的本地http服务，记录收到的请求并按路径返回预设的响应
+
+#![allow(dead_code)]
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{HeaderMap, Method, StatusCode, Uri},
+    Json, Router,
+};
+use rtxmail::{retry::RetryPolicy, Client};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// 收到的请求
+#[derive(Debug, Clone)]
+pub struct Recorded {
+    pub method: Method,
+    pub path: String,
+    pub headers: HeaderMap,
+    pub query: HashMap<String, String>,
+    pub body: Option<Value>,
+}
+
+#[derive(Default)]
+struct MockState {
+    requests: Vec<Recorded>,
+    /// 按路径预设的响应，依次返回，只剩一个时重复返回
+    responses: HashMap<String, VecDeque<(StatusCode, Value)>>,
+    /// 按路径预设的响应延迟
+    delays: HashMap<String, Duration>,
+    /// 是否已替换默认的token响应
+    token_overridden: bool,
+}
+
+#[derive(Clone)]
+pub struct MockServer {
+    pub base_url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let app = Router::new().fallback(handler).with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        state.lock().unwrap().responses.insert(
+            "/cgi-bin/gettoken".to_owned(),
+            VecDeque::from([(
+                StatusCode::OK,
+                json!({"errcode": 0, "errmsg": "ok", "access_token": "token", "expires_in": 7200}),
+            )]),
+        );
+        MockServer { base_url, state }
+    }
+
+    /// 设置`path`的响应，多次调用时按顺序依次返回
+    pub fn respond(&self, path: &str, resp: Value) -> &Self {
+        self.respond_status(path, StatusCode::OK, resp)
+    }
+
+    /// 设置`path`返回的http状态码及响应，与[`respond`](Self::respond)共用同一队列
+    pub fn respond_status(&self, path: &str, status: StatusCode, resp: Value) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        if path == "/cgi-bin/gettoken" && !state.token_overridden {
+            state.token_overridden = true;
+            state.responses.remove(path);
+        }
+        let queue = state.responses.entry(path.to_owned()).or_default();
+        queue.push_back((status, resp));
+        self
+    }
+
+    /// `path`的响应延迟`delay`后返回
+    pub fn delay(&self, path: &str, delay: Duration) -> &Self {
+        self.state
+            .lock()
+            .unwrap()
+            .delays
+            .insert(path.to_owned(), delay);
+        self
+    }
+
+    /// 收到的请求，不包含获取token的请求
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.all_requests()
+            .into_iter()
+            .filter(|r| r.path != "/cgi-bin/gettoken")
+            .collect()
+    }
+
+    pub fn all_requests(&self) -> Vec<Recorded> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// 最后一个请求
+    pub fn last_request(&self) -> Recorded {
+        self.requests().pop().expect("no request received")
+    }
+
+    pub fn client(&self) -> Client {
+        Client::builder("corp_id", "corp_secret")
+            .base_url(&self.base_url)
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap()
+    }
+}
+
+async fn handler(
+    State(state): State<Arc<Mutex<MockState>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    let path = uri.path().to_owned();
+    let delay = state.lock().unwrap().delays.get(&path).copied();
+    if let Some(delay) = delay {
+        tokio::time::sleep(delay).await;
+    }
+    let mut state = state.lock().unwrap();
+    state.requests.push(Recorded {
+        method,
+        path: path.clone(),
+        headers,
+        query,
+        body: serde_json::from_slice(&body).ok(),
+    });
+    let resp = match state.responses.get_mut(&path) {
+        Some(queue) if queue.len() > 1 => queue.pop_front(),
+        Some(queue) => queue.front().cloned(),
+        None => None,
+    };
+    let (status, resp) =
+        resp.unwrap_or_else(|| (StatusCode::OK, json!({"errcode": 0, "errmsg": "ok"})));
+    (status, Json(resp))
+}
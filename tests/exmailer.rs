@@ -0,0 +1,840 @@
+mod common;
+
+use axum::http::Method;
+use common::{MockServer, Recorded};
+use rtxmail::{client::*, errs::ApiErrorCode};
+use serde_json::{json, Value};
+
+/// 校验请求方法、路径、查询参数(包含access_token)及json body
+fn assert_request(
+    r: &Recorded,
+    method: Method,
+    path: &str,
+    query: &[(&str, &str)],
+    body: Option<Value>,
+) {
+    assert_eq!(r.method, method, "method of {path}");
+    assert_eq!(r.path, path);
+    let mut expected: Vec<(String, String)> = query
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    expected.push(("access_token".to_owned(), "token".to_owned()));
+    expected.sort();
+    let mut actual: Vec<(String, String)> = r.query.clone().into_iter().collect();
+    actual.sort();
+    assert_eq!(actual, expected, "query of {path}");
+    assert_eq!(r.body, body, "body of {path}");
+}
+
+fn department_json() -> Value {
+    json!({"id": 2, "name": "研发部", "parentid": 1, "order": 10, "path": "研发部"})
+}
+
+fn user_json() -> Value {
+    json!({
+        "userid": "zhangsan@gzdev.com",
+        "name": "张三",
+        "department": [1, 2],
+        "position": "工程师",
+        "mobile": "",
+        "gender": "1",
+        "enable": 1,
+        "slaves": [],
+    })
+}
+
+#[tokio::test]
+async fn create_department() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/department/create",
+        json!({"errcode": 0, "errmsg": "created", "id": 2}),
+    );
+    let id = server
+        .client()
+        .create_department(ParamsCreateDepartment {
+            name: "研发部".to_owned(),
+            parent_id: 1,
+            order: Some(10),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(id, 2);
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/department/create",
+        &[],
+        Some(json!({"name": "研发部", "parentid": 1, "order": 10})),
+    );
+}
+
+#[tokio::test]
+async fn update_department() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .update_department(ParamsUpdateDepartment {
+            id: 2,
+            name: Some("研发中心".to_owned()),
+            parent_id: None,
+            order: None,
+        })
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/department/update",
+        &[],
+        Some(json!({"id": 2, "name": "研发中心"})),
+    );
+}
+
+#[tokio::test]
+async fn delete_department() {
+    let server = MockServer::start().await;
+    server.client().delete_department(2).await.unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/department/delete",
+        &[("id", "2")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn list_department() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/department/list",
+        json!({"errcode": 0, "errmsg": "ok", "department": [department_json()]}),
+    );
+    let client = server.client();
+
+    let departments = client.list_department(Some(2)).await.unwrap();
+    assert_eq!(departments.len(), 1);
+    assert_eq!(departments[0].name, "研发部");
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/department/list",
+        &[("id", "2")],
+        None,
+    );
+
+    // 默认获取根部门
+    client.list_department(None).await.unwrap();
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/department/list",
+        &[("id", "1")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn search_department() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/department/search",
+        json!({"errcode": 0, "errmsg": "ok", "department": [department_json()]}),
+    );
+    let departments = server
+        .client()
+        .search_department(ParamsSerchDepartment {
+            name: "研发".to_owned(),
+            fuzzy: Some(1),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(departments[0].id, 2);
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/department/search",
+        &[],
+        Some(json!({"name": "研发", "fuzzy": 1})),
+    );
+}
+
+#[tokio::test]
+async fn create_user() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .create_user(ParamsCreateUser {
+            user_id: "zhangsan@gzdev.com".to_owned(),
+            name: "张三".to_owned(),
+            department: vec![1, 2],
+            position: None,
+            mobile: Some("13800000000".to_owned()),
+            tel: None,
+            ext_id: Some("01".to_owned()),
+            gender: None,
+            slaves: None,
+            password: "Passw0rd".to_owned(),
+            cpwd_login: Some(1),
+        })
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/user/create",
+        &[],
+        Some(json!({
+            "userid": "zhangsan@gzdev.com",
+            "name": "张三",
+            "department": [1, 2],
+            "mobile": "13800000000",
+            "extid": "01",
+            "password": "Passw0rd",
+            "cpwd_login": 1,
+        })),
+    );
+}
+
+#[tokio::test]
+async fn update_user() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .update_user(ParamsUpdateUser {
+            user_id: "zhangsan@gzdev.com".to_owned(),
+            name: None,
+            department: Some(vec![3]),
+            position: Some("经理".to_owned()),
+            mobile: None,
+            tel: None,
+            extid: None,
+            gender: None,
+            slaves: None,
+            enable: Some(0),
+            password: None,
+            cpwd_login: None,
+        })
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/user/update",
+        &[],
+        Some(json!({
+            "userid": "zhangsan@gzdev.com",
+            "department": [3],
+            "position": "经理",
+            "enable": 0,
+        })),
+    );
+}
+
+#[tokio::test]
+async fn delete_user() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .delete_user("zhangsan@gzdev.com")
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/user/delete",
+        &[("userid", "zhangsan@gzdev.com")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn get_user() {
+    let server = MockServer::start().await;
+    let mut resp = user_json();
+    resp["errcode"] = json!(0);
+    resp["errmsg"] = json!("ok");
+    server.respond("/cgi-bin/user/get", resp);
+
+    let user = server
+        .client()
+        .get_user("zhangsan@gzdev.com")
+        .await
+        .unwrap();
+    assert_eq!(user.user_id, "zhangsan@gzdev.com");
+    assert_eq!(user.department, vec![1, 2]);
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/user/get",
+        &[("userid", "zhangsan@gzdev.com")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn get_user_not_found() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/get",
+        json!({"errcode": 60111, "errmsg": "userid not found"}),
+    );
+
+    let err = server
+        .client()
+        .get_user("nobody@gzdev.com")
+        .await
+        .unwrap_err();
+    assert!(err.is_not_found());
+}
+
+#[tokio::test]
+async fn get_department_user() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/list",
+        json!({"errcode": 0, "errmsg": "ok", "userlist": [user_json()]}),
+    );
+    let users = server
+        .client()
+        .get_department_user(2, Some(true))
+        .await
+        .unwrap();
+
+    assert_eq!(users.len(), 1);
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/user/list",
+        &[("department_id", "2"), ("fetch_child", "1")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn list_department_user_simple() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/simplelist",
+        json!({"errcode": 0, "errmsg": "ok", "userlist": [
+            {"userid": "zhangsan@gzdev.com", "name": "张三", "department": [1, 2]},
+        ]}),
+    );
+    let users = server
+        .client()
+        .list_department_user_simple(2, None)
+        .await
+        .unwrap();
+
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].user_id, "zhangsan@gzdev.com");
+    assert_eq!(users[0].department, vec![1, 2]);
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/user/simplelist",
+        &[("department_id", "2"), ("fetch_child", "0")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn batchcheck_user() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/batchcheck",
+        json!({"errcode": 0, "errmsg": "ok", "list": [
+            {"user": "zhangsan@gzdev.com", "type": 1},
+            {"user": "lisi@gzdev.com", "type": 0},
+        ]}),
+    );
+    let list = server
+        .client()
+        .batchcheck_user(&["zhangsan@gzdev.com", "lisi@gzdev.com"])
+        .await
+        .unwrap();
+
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[1].kind, 0);
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/user/batchcheck",
+        &[],
+        Some(json!({"userlist": ["zhangsan@gzdev.com", "lisi@gzdev.com"]})),
+    );
+}
+
+#[tokio::test]
+async fn create_group() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .create_group(ParamsCreateGroup {
+            groupid: "dev@gzdev.com".to_owned(),
+            groupname: "dev".to_owned(),
+            userlist: Some(vec!["zhangsan@gzdev.com".to_owned()]),
+            grouplist: None,
+            department: Some(vec![2]),
+            allow_type: 0,
+            allow_userlist: None,
+        })
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/group/create",
+        &[],
+        Some(json!({
+            "groupid": "dev@gzdev.com",
+            "groupname": "dev",
+            "userlist": ["zhangsan@gzdev.com"],
+            "department": [2],
+            "allow_type": 0,
+        })),
+    );
+}
+
+#[tokio::test]
+async fn update_group() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .update_group(ParamsUpdateGroup {
+            groupid: "dev@gzdev.com".to_owned(),
+            groupname: None,
+            userlist: Some(vec!["lisi@gzdev.com".to_owned()]),
+            grouplist: None,
+            department: None,
+            allow_type: Some(1),
+            allow_userlist: None,
+        })
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/group/update",
+        &[],
+        Some(json!({
+            "groupid": "dev@gzdev.com",
+            "userlist": ["lisi@gzdev.com"],
+            "allow_type": 1,
+        })),
+    );
+}
+
+#[tokio::test]
+async fn delete_group() {
+    let server = MockServer::start().await;
+    server.client().delete_group("dev@gzdev.com").await.unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/group/delete",
+        &[("groupid", "dev@gzdev.com")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn get_group() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/group/get",
+        json!({
+            "errcode": 0,
+            "errmsg": "ok",
+            "groupid": "dev@gzdev.com",
+            "groupname": "dev",
+            "userlist": ["zhangsan@gzdev.com"],
+            "grouplist": [],
+            "department": [2],
+            "allow_type": 3,
+            "allow_userlist": ["zhangsan@gzdev.com"],
+        }),
+    );
+    let group = server.client().get_group("dev@gzdev.com").await.unwrap();
+
+    assert_eq!(group.groupid, "dev@gzdev.com");
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/group/get",
+        &[("groupid", "dev@gzdev.com")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn get_login_url() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/service/get_login_url",
+        json!({
+            "errcode": 0,
+            "errmsg": "ok",
+            "login_url": "https://exmail.qq.com/cgi-bin/login?fun=bizopenssologin",
+            "expires_in": 300,
+        }),
+    );
+    let url = server
+        .client()
+        .get_login_url("zhangsan@gzdev.com")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        url.login_url,
+        "https://exmail.qq.com/cgi-bin/login?fun=bizopenssologin"
+    );
+    assert!(!url.is_expired());
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/service/get_login_url",
+        &[("userid", "zhangsan@gzdev.com")],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn get_new_mail_count() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/mail/newcount",
+        json!({"errcode": 0, "errmsg": "ok", "count": 3}),
+    );
+    let count = server
+        .client()
+        .get_new_mail_count(
+            "zhangsan@gzdev.com",
+            "2022-07-01".parse().unwrap(),
+            "2022-07-07".parse().unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(count.count, 3);
+    assert_request(
+        &server.last_request(),
+        Method::GET,
+        "/cgi-bin/mail/newcount",
+        &[
+            ("userid", "zhangsan@gzdev.com"),
+            ("begin_date", "2022-07-01"),
+            ("end_date", "2022-07-07"),
+        ],
+        None,
+    );
+}
+
+#[tokio::test]
+async fn get_new_mail_counts() {
+    let server = MockServer::start().await;
+    server
+        .respond(
+            "/cgi-bin/mail/newcount",
+            json!({"errcode": 0, "errmsg": "ok", "count": 3}),
+        )
+        .respond(
+            "/cgi-bin/mail/newcount",
+            json!({"errcode": 60111, "errmsg": "userid not found"}),
+        );
+    let users = ["zhangsan@gzdev.com", "nobody@gzdev.com"];
+    let counts = server
+        .client()
+        .get_new_mail_counts(
+            &users,
+            "2022-07-01".parse().unwrap(),
+            "2022-07-07".parse().unwrap(),
+            1,
+        )
+        .await;
+
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[0].0, "zhangsan@gzdev.com");
+    assert_eq!(counts[0].1.as_ref().unwrap().count, 3);
+    assert_eq!(counts[1].0, "nobody@gzdev.com");
+    assert!(counts[1].1.as_ref().unwrap_err().is_not_found());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn get_user_option() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/useroption/get",
+        json!({"errcode": 0, "errmsg": "ok", "option": [
+            {"type": 1, "value": "0"},
+            {"type": 2, "value": "1"},
+        ]}),
+    );
+    let client = server.client();
+    let options = client
+        .get_user_option(
+            "zhangsan@gzdev.com",
+            &[UserOption::ForceSecureLogin, UserOption::ImapSmtp],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        options,
+        [
+            UserOptionValue::new(UserOption::ForceSecureLogin, false),
+            UserOptionValue::new(UserOption::ImapSmtp, true),
+        ]
+    );
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/useroption/get",
+        &[],
+        Some(json!({"userid": "zhangsan@gzdev.com", "type": [1, 2]})),
+    );
+
+    // 未指定时查询全部属性
+    client
+        .get_user_option("zhangsan@gzdev.com", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        server.last_request().body,
+        Some(json!({"userid": "zhangsan@gzdev.com", "type": [1, 2, 3, 4]}))
+    );
+}
+
+#[tokio::test]
+async fn update_user_option() {
+    let server = MockServer::start().await;
+    server
+        .client()
+        .update_user_option(
+            "zhangsan@gzdev.com",
+            &[
+                UserOptionValue::new(UserOption::PopSmtp, false),
+                UserOptionValue::new(UserOption::ForceSecureLogin, true),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/useroption/update",
+        &[],
+        Some(json!({
+            "userid": "zhangsan@gzdev.com",
+            "option": [{"type": 3, "value": "0"}, {"type": 1, "value": "1"}],
+        })),
+    );
+}
+
+fn new_user(user_id: &str) -> ParamsCreateUser {
+    ParamsCreateUser {
+        user_id: user_id.to_owned(),
+        name: user_id.to_owned(),
+        department: vec![1],
+        position: None,
+        mobile: None,
+        tel: None,
+        ext_id: None,
+        gender: None,
+        slaves: None,
+        password: "Passw0rd".to_owned(),
+        cpwd_login: None,
+    }
+}
+
+fn respond_ok_err_ok(server: &MockServer, path: &str) {
+    server
+        .respond(path, json!({"errcode": 0, "errmsg": "ok"}))
+        .respond(path, json!({"errcode": 60102, "errmsg": "userid existed"}))
+        .respond(path, json!({"errcode": 0, "errmsg": "ok"}));
+}
+
+#[tokio::test]
+async fn create_users_reports_each_user() {
+    let server = MockServer::start().await;
+    respond_ok_err_ok(&server, "/cgi-bin/user/create");
+    let users = ["a@gzdev.com", "b@gzdev.com", "c@gzdev.com"];
+    let report = server
+        .client()
+        .create_users(
+            users.iter().map(|id| new_user(id)).collect(),
+            BatchOptions {
+                concurrency: 1,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    assert!(!report.is_success());
+    assert!(report.skipped.is_empty());
+    assert_eq!(
+        report.succeeded().collect::<Vec<_>>(),
+        ["a@gzdev.com", "c@gzdev.com"]
+    );
+    let failed: Vec<_> = report.failed().collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0, "b@gzdev.com");
+    assert_eq!(failed[0].1.api_code(), Some(ApiErrorCode::from(60102)));
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn update_users_stop_on_first_failure() {
+    let server = MockServer::start().await;
+    respond_ok_err_ok(&server, "/cgi-bin/user/update");
+    let users = ["a@gzdev.com", "b@gzdev.com", "c@gzdev.com"];
+    let report = server
+        .client()
+        .update_users(
+            users
+                .iter()
+                .map(|id| ParamsUpdateUser {
+                    user_id: id.to_string(),
+                    name: None,
+                    department: None,
+                    position: None,
+                    mobile: None,
+                    tel: None,
+                    extid: None,
+                    gender: None,
+                    slaves: None,
+                    enable: Some(0),
+                    password: None,
+                    cpwd_login: None,
+                })
+                .collect(),
+            BatchOptions {
+                concurrency: 1,
+                stop_on_first_failure: true,
+            },
+        )
+        .await;
+
+    assert_eq!(report.results.len(), 2);
+    assert_eq!(report.failed().next().unwrap().0, "b@gzdev.com");
+    assert_eq!(report.skipped, ["c@gzdev.com"]);
+    assert_eq!(server.requests().len(), 2);
+}
+
+fn group_json(userlist: &[&str]) -> Value {
+    json!({
+        "errcode": 0,
+        "errmsg": "ok",
+        "groupid": "dev@gzdev.com",
+        "groupname": "dev",
+        "userlist": userlist,
+        "grouplist": [],
+        "department": [2],
+        "allow_type": 0,
+        "allow_userlist": [],
+    })
+}
+
+#[tokio::test]
+async fn add_group_members() {
+    let server = MockServer::start().await;
+    server.respond("/cgi-bin/group/get", group_json(&["a@gzdev.com"]));
+    server
+        .client()
+        .add_group_members("dev@gzdev.com", &["a@gzdev.com", "b@gzdev.com"])
+        .await
+        .unwrap();
+
+    assert_request(
+        &server.last_request(),
+        Method::POST,
+        "/cgi-bin/group/update",
+        &[],
+        Some(json!({"groupid": "dev@gzdev.com", "userlist": ["a@gzdev.com", "b@gzdev.com"]})),
+    );
+}
+
+#[tokio::test]
+async fn remove_group_departments() {
+    let server = MockServer::start().await;
+    server.respond("/cgi-bin/group/get", group_json(&[]));
+    let client = server.client();
+    client
+        .remove_group_departments("dev@gzdev.com", &[2])
+        .await
+        .unwrap();
+    assert_eq!(
+        server.last_request().body,
+        Some(json!({"groupid": "dev@gzdev.com", "department": []}))
+    );
+
+    // 无需修改时不写入
+    let before = server.requests().len();
+    client
+        .remove_group_subgroups("dev@gzdev.com", &["other@gzdev.com"])
+        .await
+        .unwrap();
+    let requests = server.requests();
+    assert_eq!(requests.len(), before + 1);
+    assert_eq!(requests[before].path, "/cgi-bin/group/get");
+}
+
+#[tokio::test]
+async fn group_members_conflict() {
+    let server = MockServer::start().await;
+    // 每次写入前重新读取的结果都与修改前不同
+    for _ in 0..GROUP_UPDATE_MAX_ATTEMPTS {
+        server
+            .respond("/cgi-bin/group/get", group_json(&["a@gzdev.com"]))
+            .respond("/cgi-bin/group/get", group_json(&["c@gzdev.com"]));
+    }
+    let err = server
+        .client()
+        .add_group_members("dev@gzdev.com", &["b@gzdev.com"])
+        .await
+        .unwrap_err();
+
+    assert!(err.is_conflict());
+    assert!(server
+        .requests()
+        .iter()
+        .all(|r| r.path == "/cgi-bin/group/get"));
+}
+
+#[tokio::test]
+async fn group_members_retry_after_conflict() {
+    let server = MockServer::start().await;
+    server
+        .respond("/cgi-bin/group/get", group_json(&["a@gzdev.com"]))
+        .respond(
+            "/cgi-bin/group/get",
+            group_json(&["a@gzdev.com", "c@gzdev.com"]),
+        );
+    server
+        .client()
+        .add_group_members("dev@gzdev.com", &["b@gzdev.com"])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        server.last_request().body,
+        Some(json!({
+            "groupid": "dev@gzdev.com",
+            "userlist": ["a@gzdev.com", "c@gzdev.com", "b@gzdev.com"],
+        }))
+    );
+}
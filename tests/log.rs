@@ -0,0 +1,219 @@
+mod common;
+
+use axum::http::Method;
+use common::MockServer;
+use rtxmail::client::*;
+use serde_json::json;
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+#[tokio::test]
+async fn get_mail_status() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/log/mailstatus",
+        json!({"errcode": 0, "errmsg": "ok", "sendsum": 12, "recvsum": 34}),
+    );
+    let status = server
+        .client()
+        .get_mail_status(ParamsMailStatus {
+            domain: "gzdev.com".to_owned(),
+            begin_date: date("2022-07-01"),
+            end_date: date("2022-07-31"),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        status,
+        MailStatus {
+            sent: 12,
+            received: 34
+        }
+    );
+    let r = server.last_request();
+    assert_eq!(r.method, Method::POST);
+    assert_eq!(r.path, "/cgi-bin/log/mailstatus");
+    assert_eq!(
+        r.body,
+        Some(json!({"domain": "gzdev.com", "begin_date": "2022-07-01", "end_date": "2022-07-31"}))
+    );
+}
+
+#[tokio::test]
+async fn list_mail_log() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/log/mail",
+        json!({"errcode": 0, "errmsg": "ok", "list": [{
+            "mailtype": 1,
+            "sender": "zhangsan@gzdev.com",
+            "receiver": "lisi@qq.com",
+            "time": 1656633600,
+            "status": 3,
+            "subject": "周报",
+        }]}),
+    );
+    let logs = server
+        .client()
+        .list_mail_log(ParamsMailLog {
+            domain: "gzdev.com".to_owned(),
+            begin_date: date("2022-07-01"),
+            end_date: date("2022-07-02"),
+            mail_type: MailType::Sent,
+            user_id: Some("zhangsan@gzdev.com".to_owned()),
+            subject: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].mail_type, MailType::Sent);
+    assert_eq!(logs[0].status, MailLogStatus::Sent);
+    assert!(logs[0].status.is_delivered());
+    assert_eq!(logs[0].time.timestamp(), 1656633600);
+    let r = server.last_request();
+    assert_eq!(r.method, Method::POST);
+    assert_eq!(r.path, "/cgi-bin/log/mail");
+    assert_eq!(
+        r.body,
+        Some(json!({
+            "domain": "gzdev.com",
+            "begin_date": "2022-07-01",
+            "end_date": "2022-07-02",
+            "mailtype": 1,
+            "userid": "zhangsan@gzdev.com",
+        }))
+    );
+}
+
+#[tokio::test]
+async fn list_login_log_splits_long_range() {
+    let server = MockServer::start().await;
+    server
+        .respond(
+            "/cgi-bin/log/login",
+            json!({"errcode": 0, "errmsg": "ok", "list": [
+                {"time": 1656633600, "ip": "10.0.0.1", "type": 1},
+            ]}),
+        )
+        .respond(
+            "/cgi-bin/log/login",
+            json!({"errcode": 0, "errmsg": "ok", "list": [
+                {"time": 1659312000, "ip": "10.0.0.2", "type": 4},
+            ]}),
+        );
+    let logs = server
+        .client()
+        .list_login_log(ParamsLoginLog {
+            user_id: "zhangsan@gzdev.com".to_owned(),
+            begin_date: date("2022-07-01"),
+            end_date: date("2022-08-14"),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0].login_type, LoginType::Web);
+    assert_eq!(logs[1].login_type, LoginType::Client);
+    assert_eq!(logs[1].ip, "10.0.0.2");
+
+    let bodies: Vec<_> = server.requests().into_iter().map(|r| r.body).collect();
+    assert_eq!(
+        bodies,
+        [
+            Some(
+                json!({"userid": "zhangsan@gzdev.com", "begin_date": "2022-07-01", "end_date": "2022-07-30"})
+            ),
+            Some(
+                json!({"userid": "zhangsan@gzdev.com", "begin_date": "2022-07-31", "end_date": "2022-08-14"})
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn list_batch_job_log() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/log/batchjob",
+        json!({"errcode": 0, "errmsg": "ok", "list": [
+            {"time": 1656633600, "operator": "admin@gzdev.com", "type": 1},
+        ]}),
+    );
+    let logs = server
+        .client()
+        .list_batch_job_log(ParamsBatchJobLog {
+            begin_date: date("2022-07-01"),
+            end_date: date("2022-07-07"),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(logs[0].operator, "admin@gzdev.com");
+    assert_eq!(logs[0].job_type, BatchJobType::ImportUsers);
+    let r = server.last_request();
+    assert_eq!(r.path, "/cgi-bin/log/batchjob");
+    assert_eq!(
+        r.body,
+        Some(json!({"begin_date": "2022-07-01", "end_date": "2022-07-07"}))
+    );
+}
+
+#[tokio::test]
+async fn list_operation_log() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/log/operation",
+        json!({"errcode": 0, "errmsg": "ok", "list": [
+            {"time": 1656633600, "operator": "admin@gzdev.com", "type": 2, "operation": "添加成员 zhangsan@gzdev.com"},
+            {"time": 1656633601, "operator": "admin@gzdev.com", "type": 99},
+        ]}),
+    );
+    let logs = server
+        .client()
+        .list_operation_log(ParamsOperationLog {
+            operation_type: OperationType::All,
+            begin_date: date("2022-07-01"),
+            end_date: date("2022-07-07"),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(logs[0].operation_type, OperationType::Account);
+    assert_eq!(logs[0].operation, "添加成员 zhangsan@gzdev.com");
+    assert_eq!(logs[1].operation_type, OperationType::Unknown(99));
+    let r = server.last_request();
+    assert_eq!(r.path, "/cgi-bin/log/operation");
+    assert_eq!(
+        r.body,
+        Some(json!({"type": 1, "begin_date": "2022-07-01", "end_date": "2022-07-07"}))
+    );
+}
+
+#[tokio::test]
+async fn reject_swapped_date_range() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    let err = client
+        .list_login_log(ParamsLoginLog {
+            user_id: "zhangsan@gzdev.com".to_owned(),
+            begin_date: date("2022-08-01"),
+            end_date: date("2022-07-01"),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, rtxmail::errs::Error::InvalidArgument { .. }));
+
+    let err = client
+        .list_batch_job_log(ParamsBatchJobLog {
+            begin_date: date("2022-08-01"),
+            end_date: date("2022-07-01"),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, rtxmail::errs::Error::InvalidArgument { .. }));
+    assert!(server.all_requests().is_empty());
+}
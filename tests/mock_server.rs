@@ -0,0 +1,110 @@
+use rtxmail::{
+    client::*,
+    errs::ApiErrorCode,
+    retry::RetryPolicy,
+    testing::server::{MockConfig, MockExmailServer},
+    Client,
+};
+use std::time::Duration;
+
+async fn start(config: MockConfig) -> (MockExmailServer, Client) {
+    let server = MockExmailServer::new(config);
+    let addr = server.start("127.0.0.1:0").await.unwrap();
+    let client = Client::builder("corp_id", "corp_secret")
+        .base_url(format!("http://{addr}"))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    (server, client)
+}
+
+#[tokio::test]
+async fn directory_over_http() {
+    let (server, client) = start(MockConfig::default()).await;
+    let id = client
+        .create_department(ParamsCreateDepartment {
+            name: "研发部".to_owned(),
+            parent_id: 1,
+            order: Some(10),
+        })
+        .await
+        .unwrap();
+    client
+        .create_user(ParamsCreateUser {
+            user_id: "zhangsan@gzdev.com".to_owned(),
+            name: "张三".to_owned(),
+            department: vec![id],
+            position: None,
+            mobile: None,
+            tel: None,
+            ext_id: None,
+            gender: None,
+            slaves: None,
+            password: "Passw0rd".to_owned(),
+            cpwd_login: None,
+        })
+        .await
+        .unwrap();
+
+    let users = client.get_department_user(id, None).await.unwrap();
+    assert_eq!(users[0].user_id, "zhangsan@gzdev.com");
+    let err = client.delete_department(id).await.unwrap_err();
+    assert_eq!(err.api_code(), Some(ApiErrorCode::DepartmentHasUsers));
+
+    // 服务与FakeExmail共享数据
+    let departments = server.fake().list_department(None).await.unwrap();
+    assert_eq!(departments.len(), 2);
+}
+
+#[tokio::test]
+async fn rejects_wrong_secret() {
+    let server = MockExmailServer::new(MockConfig::default());
+    let addr = server.start("127.0.0.1:0").await.unwrap();
+    let client = Client::builder("corp_id", "wrong")
+        .base_url(format!("http://{addr}"))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let err = client.list_department(None).await.unwrap_err();
+    assert!(err.is_auth_error());
+}
+
+#[tokio::test]
+async fn refreshes_expired_token() {
+    let (server, client) = start(MockConfig::default()).await;
+    client.list_department(None).await.unwrap();
+
+    server.expire_tokens();
+    client.list_department(None).await.unwrap();
+}
+
+#[tokio::test]
+async fn injected_errors_and_latency() {
+    let (server, client) = start(MockConfig {
+        latency: Duration::from_millis(50),
+        ..Default::default()
+    })
+    .await;
+
+    server.inject_errors(ApiErrorCode::RateLimited, 1);
+    let err = client.list_department(None).await.unwrap_err();
+    assert!(err.is_retryable());
+
+    let started = std::time::Instant::now();
+    client.list_department(None).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn error_rate() {
+    let (_server, client) = start(MockConfig {
+        error_rate: 1.0,
+        error_code: ApiErrorCode::SystemBusy,
+        ..Default::default()
+    })
+    .await;
+
+    let err = client.list_department(None).await.unwrap_err();
+    assert_eq!(err.api_code(), Some(ApiErrorCode::SystemBusy));
+}
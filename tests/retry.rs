@@ -0,0 +1,105 @@
+mod common;
+
+use axum::http::StatusCode;
+use common::MockServer;
+use rtxmail::{client::Exmailer, dto::ParamsCreateUser, retry::RetryPolicy, Client};
+use serde_json::json;
+use std::time::Duration;
+
+fn client(server: &MockServer) -> Client {
+    Client::builder("corp_id", "corp_secret")
+        .base_url(&server.base_url)
+        .retry_policy(RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            jitter: false,
+            ..Default::default()
+        })
+        .build()
+        .unwrap()
+}
+
+fn paths(server: &MockServer) -> Vec<String> {
+    server.all_requests().into_iter().map(|r| r.path).collect()
+}
+
+#[tokio::test]
+async fn get_is_retried_when_busy() {
+    let server = MockServer::start().await;
+    server
+        .respond(
+            "/cgi-bin/department/list",
+            json!({"errcode": -1, "errmsg": "system busy"}),
+        )
+        .respond(
+            "/cgi-bin/department/list",
+            json!({"errcode": 0, "errmsg": "ok", "department": [
+                {"id": 1, "name": "企业", "parentid": 0, "order": 0},
+            ]}),
+        );
+    let departments = client(&server).list_department(None).await.unwrap();
+    assert_eq!(departments[0].name, "企业");
+    assert_eq!(
+        paths(&server),
+        [
+            "/cgi-bin/gettoken",
+            "/cgi-bin/department/list",
+            "/cgi-bin/department/list"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn create_is_not_retried_by_default() {
+    let server = MockServer::start().await;
+    server
+        .respond(
+            "/cgi-bin/user/create",
+            json!({"errcode": -1, "errmsg": "system busy"}),
+        )
+        .respond(
+            "/cgi-bin/user/create",
+            json!({"errcode": 0, "errmsg": "ok"}),
+        );
+    let err = client(&server)
+        .create_user(ParamsCreateUser {
+            user_id: "zhangsan@gzdev.com".to_owned(),
+            name: "张三".to_owned(),
+            department: vec![1],
+            position: None,
+            mobile: None,
+            tel: None,
+            ext_id: None,
+            gender: None,
+            slaves: None,
+            password: "Passw0rd".to_owned(),
+            cpwd_login: None,
+        })
+        .await
+        .unwrap_err();
+    assert!(err.is_retryable());
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn gettoken_is_retried_on_server_error() {
+    let server = MockServer::start().await;
+    server
+        .respond_status(
+            "/cgi-bin/gettoken",
+            StatusCode::SERVICE_UNAVAILABLE,
+            json!({}),
+        )
+        .respond(
+            "/cgi-bin/gettoken",
+            json!({"errcode": 0, "errmsg": "ok", "access_token": "token", "expires_in": 7200}),
+        );
+    client(&server).delete_user("a@gzdev.com").await.unwrap();
+    assert_eq!(
+        paths(&server),
+        [
+            "/cgi-bin/gettoken",
+            "/cgi-bin/gettoken",
+            "/cgi-bin/user/delete"
+        ]
+    );
+}
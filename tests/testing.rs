@@ -0,0 +1,295 @@
+use rtxmail::{client::*, errs::ApiErrorCode, testing::FakeExmail};
+
+fn code<T: std::fmt::Debug>(result: rtxmail::errs::Result<T>) -> ApiErrorCode {
+    result.unwrap_err().api_code().unwrap()
+}
+
+fn department(name: &str, parent_id: u64) -> ParamsCreateDepartment {
+    ParamsCreateDepartment {
+        name: name.to_owned(),
+        parent_id,
+        order: None,
+    }
+}
+
+fn user(user_id: &str, department: Vec<u64>) -> ParamsCreateUser {
+    ParamsCreateUser {
+        user_id: user_id.to_owned(),
+        name: "张三".to_owned(),
+        department,
+        position: None,
+        mobile: None,
+        tel: None,
+        ext_id: None,
+        gender: None,
+        slaves: None,
+        password: "Passw0rd".to_owned(),
+        cpwd_login: None,
+    }
+}
+
+fn group(groupid: &str, userlist: &[&str]) -> ParamsCreateGroup {
+    ParamsCreateGroup {
+        groupid: groupid.to_owned(),
+        groupname: "dev".to_owned(),
+        userlist: Some(userlist.iter().map(|u| u.to_string()).collect()),
+        grouplist: None,
+        department: None,
+        allow_type: 0,
+        allow_userlist: None,
+    }
+}
+
+#[tokio::test]
+async fn department_rules() {
+    let fake = FakeExmail::new();
+    let dev = fake
+        .create_department(department("研发部", 1))
+        .await
+        .unwrap();
+    let web = fake
+        .create_department(department("前端", dev))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        code(fake.create_department(department("测试", 99)).await),
+        ApiErrorCode::ParentDepartmentNotFound
+    );
+    assert_eq!(
+        code(fake.create_department(department("研发部", 1)).await),
+        ApiErrorCode::DepartmentNameExists
+    );
+    assert_eq!(
+        code(
+            fake.update_department(ParamsUpdateDepartment {
+                id: dev,
+                name: None,
+                parent_id: Some(web),
+                order: None,
+            })
+            .await
+        ),
+        ApiErrorCode::DepartmentCycle
+    );
+
+    let ids: Vec<u64> = fake
+        .list_department(Some(dev))
+        .await
+        .unwrap()
+        .iter()
+        .map(|d| d.id)
+        .collect();
+    assert_eq!(ids, [dev, web]);
+
+    assert_eq!(
+        code(fake.delete_department(1).await),
+        ApiErrorCode::DeleteRootDepartment
+    );
+    assert_eq!(
+        code(fake.delete_department(dev).await),
+        ApiErrorCode::DepartmentHasChildren
+    );
+    fake.create_user(user("zhangsan@gzdev.com", vec![web]))
+        .await
+        .unwrap();
+    assert_eq!(
+        code(fake.delete_department(web).await),
+        ApiErrorCode::DepartmentHasUsers
+    );
+    fake.delete_user("zhangsan@gzdev.com").await.unwrap();
+    fake.delete_department(web).await.unwrap();
+    fake.delete_department(dev).await.unwrap();
+    assert!(fake
+        .delete_department(dev)
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[tokio::test]
+async fn user_rules() {
+    let fake = FakeExmail::new();
+    assert_eq!(
+        code(fake.create_user(user("zhangsan", vec![1])).await),
+        ApiErrorCode::InvalidUserId
+    );
+    assert_eq!(
+        code(fake.create_user(user("zhangsan@gzdev.com", vec![2])).await),
+        ApiErrorCode::InvalidDepartmentId
+    );
+    assert_eq!(
+        code(
+            fake.create_user(user("zhangsan@gzdev.com", vec![1; 21]))
+                .await
+        ),
+        ApiErrorCode::InvalidField
+    );
+    let mut params = user("zhangsan@gzdev.com", vec![1]);
+    params.slaves = Some((0..6).map(|i| format!("s{i}@gzdev.com")).collect());
+    assert_eq!(
+        code(fake.create_user(params).await),
+        ApiErrorCode::InvalidField
+    );
+
+    let mut params = user("zhangsan@gzdev.com", vec![1]);
+    params.slaves = Some(vec!["zs@gzdev.com".to_owned()]);
+    fake.create_user(params).await.unwrap();
+    assert_eq!(
+        code(fake.create_user(user("zs@gzdev.com", vec![1])).await),
+        ApiErrorCode::UserIdExists
+    );
+
+    let checks = fake
+        .batchcheck_user(&["zhangsan@gzdev.com", "zs@gzdev.com", "lisi@gzdev.com"])
+        .await
+        .unwrap();
+    let kinds: Vec<u8> = checks.iter().map(|c| c.kind).collect();
+    assert_eq!(kinds, [1, 2, 0]);
+
+    fake.update_user(ParamsUpdateUser {
+        user_id: "zhangsan@gzdev.com".to_owned(),
+        name: None,
+        department: None,
+        position: Some("经理".to_owned()),
+        mobile: None,
+        tel: None,
+        extid: None,
+        gender: None,
+        slaves: None,
+        enable: Some(0),
+        password: None,
+        cpwd_login: None,
+    })
+    .await
+    .unwrap();
+    let user = fake.get_user("zhangsan@gzdev.com").await.unwrap();
+    assert_eq!((user.position.as_str(), user.enable), ("经理", 0));
+    assert!(fake
+        .get_user("lisi@gzdev.com")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[tokio::test]
+async fn group_rules() {
+    let fake = FakeExmail::new();
+    fake.create_user(user("zhangsan@gzdev.com", vec![1]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        code(
+            fake.create_group(group("dev", &["zhangsan@gzdev.com"]))
+                .await
+        ),
+        ApiErrorCode::InvalidField
+    );
+    assert_eq!(
+        code(
+            fake.create_group(group("dev@gzdev.com", &["lisi@gzdev.com"]))
+                .await
+        ),
+        ApiErrorCode::UserNotFound
+    );
+    fake.create_group(group("dev@gzdev.com", &["zhangsan@gzdev.com"]))
+        .await
+        .unwrap();
+    assert_eq!(
+        code(
+            fake.create_group(group("dev@gzdev.com", &["zhangsan@gzdev.com"]))
+                .await
+        ),
+        ApiErrorCode::UserIdExists
+    );
+
+    // ExmailerExt同样适用于FakeExmail
+    fake.create_user(user("lisi@gzdev.com", vec![1]))
+        .await
+        .unwrap();
+    fake.add_group_members("dev@gzdev.com", &["lisi@gzdev.com"])
+        .await
+        .unwrap();
+    fake.delete_user("zhangsan@gzdev.com").await.unwrap();
+    let group = fake.get_group("dev@gzdev.com").await.unwrap();
+    assert_eq!(group.userlist, ["lisi@gzdev.com"]);
+
+    fake.delete_group("dev@gzdev.com").await.unwrap();
+    assert!(fake
+        .get_group("dev@gzdev.com")
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
+
+#[tokio::test]
+async fn user_option_and_mail_count() {
+    let fake = FakeExmail::new();
+    fake.create_user(user("zhangsan@gzdev.com", vec![1]))
+        .await
+        .unwrap();
+    fake.update_user_option(
+        "zhangsan@gzdev.com",
+        &[UserOptionValue::new(UserOption::ImapSmtp, true)],
+    )
+    .await
+    .unwrap();
+    let options = fake
+        .get_user_option(
+            "zhangsan@gzdev.com",
+            &[UserOption::ImapSmtp, UserOption::PopSmtp],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        options,
+        [
+            UserOptionValue::new(UserOption::ImapSmtp, true),
+            UserOptionValue::new(UserOption::PopSmtp, false),
+        ]
+    );
+
+    fake.directory()
+        .set_new_mail_count("zhangsan@gzdev.com", 5)
+        .unwrap();
+    let date = "2022-07-01".parse().unwrap();
+    let count = fake
+        .get_new_mail_count("zhangsan@gzdev.com", date, date)
+        .await
+        .unwrap();
+    assert_eq!(count.count, 5);
+}
+
+#[tokio::test]
+async fn department_tree() {
+    let fake = FakeExmail::new();
+    let sales = fake
+        .create_department(department("Sales", 1))
+        .await
+        .unwrap();
+    let east = fake
+        .create_department(department("East", sales))
+        .await
+        .unwrap();
+    let team = fake
+        .create_department(department("Team1", east))
+        .await
+        .unwrap();
+
+    let tree = fake.get_department_tree(None).await.unwrap();
+    assert!(tree.is_consistent());
+    assert_eq!(tree.find_by_path("Sales/East/Team1").unwrap().id, team);
+    assert_eq!(tree.path(team).unwrap(), "Sales/East/Team1");
+
+    let subtree = fake.get_department_tree(Some(sales)).await.unwrap();
+    assert_eq!(subtree.root().id, sales);
+    let ids: Vec<u64> = subtree.dfs_post_order().map(|d| d.id).collect();
+    assert_eq!(ids, [team, east, sales]);
+
+    assert!(fake
+        .get_department_tree(Some(99))
+        .await
+        .unwrap_err()
+        .is_not_found());
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::MockServer;
+use rtxmail::{client::Exmailer, retry::RetryPolicy, token::FileTokenStore, Client};
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+
+#[tokio::test]
+async fn token_is_requested_once_and_cached() {
+    let server = MockServer::start().await;
+    let client = server.client();
+    client.delete_user("a@gzdev.com").await.unwrap();
+    client.delete_user("b@gzdev.com").await.unwrap();
+
+    let requests = server.all_requests();
+    let token_requests: Vec<_> = requests
+        .iter()
+        .filter(|r| r.path == "/cgi-bin/gettoken")
+        .collect();
+    assert_eq!(token_requests.len(), 1);
+    assert_eq!(token_requests[0].method, axum::http::Method::GET);
+    assert_eq!(token_requests[0].query["corpid"], "corp_id");
+    assert_eq!(token_requests[0].query["corpsecret"], "corp_secret");
+    assert!(token_requests[0].body.is_none());
+}
+
+#[tokio::test]
+async fn expired_token_is_refreshed_and_request_retried_once() {
+    let server = MockServer::start().await;
+    server
+        .respond(
+            "/cgi-bin/user/delete",
+            json!({"errcode": 42001, "errmsg": "access_token expired"}),
+        )
+        .respond(
+            "/cgi-bin/user/delete",
+            json!({"errcode": 0, "errmsg": "ok"}),
+        );
+    let client = server.client();
+    client.delete_user("a@gzdev.com").await.unwrap();
+
+    let paths: Vec<_> = server.all_requests().into_iter().map(|r| r.path).collect();
+    assert_eq!(
+        paths,
+        [
+            "/cgi-bin/gettoken",
+            "/cgi-bin/user/delete",
+            "/cgi-bin/gettoken",
+            "/cgi-bin/user/delete",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn invalid_token_is_not_retried_forever() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/delete",
+        json!({"errcode": 40014, "errmsg": "invalid access_token"}),
+    );
+    let err = server
+        .client()
+        .delete_user("a@gzdev.com")
+        .await
+        .unwrap_err();
+
+    assert!(err.is_auth_error());
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn token_error_is_returned() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/gettoken",
+        json!({"errcode": 40001, "errmsg": "invalid credential"}),
+    );
+    let err = server
+        .client()
+        .delete_user("a@gzdev.com")
+        .await
+        .unwrap_err();
+
+    assert!(err.is_auth_error());
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_requests_share_one_token_refresh() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/user/get",
+        json!({
+            "errcode": 0,
+            "errmsg": "ok",
+            "userid": "zhangsan@gzdev.com",
+            "name": "张三",
+            "department": [1],
+            "position": "",
+            "mobile": "",
+            "enable": 1,
+            "slaves": [],
+        }),
+    );
+    let client = server.client();
+    let requests = (0..16).map(|i| {
+        let client = client.clone();
+        async move { client.get_user(&format!("user{i}@gzdev.com")).await }
+    });
+    let results = futures::future::join_all(requests).await;
+    for result in results {
+        result.unwrap();
+    }
+
+    let token_requests = server
+        .all_requests()
+        .into_iter()
+        .filter(|r| r.path == "/cgi-bin/gettoken")
+        .count();
+    assert_eq!(token_requests, 1);
+    assert_eq!(server.requests().len(), 16);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn file_store_shares_one_token_refresh_between_clients() {
+    let path =
+        std::env::temp_dir().join(format!("rtxmail-shared-token-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let server = MockServer::start().await;
+    // 模拟两个进程：各自的Client及FileTokenStore实例使用同一个文件
+    let clients: Vec<Client> = (0..2)
+        .map(|_| {
+            Client::builder("corp_id", "corp_secret")
+                .base_url(&server.base_url)
+                .retry_policy(RetryPolicy::none())
+                .token_store(Arc::new(FileTokenStore::new(&path)))
+                .build()
+                .unwrap()
+        })
+        .collect();
+    let requests = clients.iter().flat_map(|client| {
+        (0..8).map(move |i| {
+            let client = client.clone();
+            async move { client.delete_user(&format!("user{i}@gzdev.com")).await }
+        })
+    });
+    for result in futures::future::join_all(requests).await {
+        result.unwrap();
+    }
+
+    let token_requests = server
+        .all_requests()
+        .into_iter()
+        .filter(|r| r.path == "/cgi-bin/gettoken")
+        .count();
+    assert_eq!(token_requests, 1);
+    assert_eq!(server.requests().len(), 16);
+
+    let mut lock_path = path.clone().into_os_string();
+    lock_path.push(".lock");
+    let _ = std::fs::remove_file(lock_path);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn waiting_requests_share_token_refresh_failure() {
+    let server = MockServer::start().await;
+    server.respond(
+        "/cgi-bin/gettoken",
+        json!({"errcode": -1, "errmsg": "system busy"}),
+    );
+    let client = Client::builder("corp_id", "corp_secret")
+        .base_url(&server.base_url)
+        .retry_policy(RetryPolicy {
+            base_delay: Duration::from_millis(20),
+            jitter: false,
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    let requests = (0..8).map(|i| {
+        let client = client.clone();
+        async move { client.delete_user(&format!("user{i}@gzdev.com")).await }
+    });
+    for result in futures::future::join_all(requests).await {
+        assert!(result.unwrap_err().is_retryable());
+    }
+
+    // 只有一个任务按重试策略请求，等待的任务共享其失败结果
+    let token_requests = server.all_requests().len();
+    assert_eq!(token_requests, RetryPolicy::default().max_attempts as usize);
+}